@@ -1,13 +1,21 @@
 use std::fmt::Display;
 
-use crate::token::token::Token;
+use crate::{
+    object::bigint::BigInt,
+    token::token::{Span, Token},
+};
 
 /** PROGRAM */
-
 pub struct Program {
     pub statements: Vec<Statement>,
 }
 
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Program {
     pub fn new() -> Self {
         Program {
@@ -31,34 +39,95 @@ impl Display for Program {
 
 /** STATEMENTS */
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct LetStatement {
     pub name: Identifier,
     pub value: Expression,
+    pub span: Span,
 }
 
 impl LetStatement {
-    pub(crate) fn new(name: Identifier, value: Expression) -> Self {
-        LetStatement { name, value }
+    pub(crate) fn new(name: Identifier, value: Expression, span: Span) -> Self {
+        LetStatement { name, value, span }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct ReturnStatement {
     pub value: Expression,
+    pub span: Span,
 }
 
 impl ReturnStatement {
-    pub(crate) fn new(value: Expression) -> Self {
-        ReturnStatement { value }
+    pub(crate) fn new(value: Expression, span: Span) -> Self {
+        ReturnStatement { value, span }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WhileStatement {
+    pub condition: Expression,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+impl WhileStatement {
+    pub(crate) fn new(condition: Expression, body: Vec<Statement>, span: Span) -> Self {
+        WhileStatement {
+            condition,
+            body,
+            span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForStatement {
+    pub variable: Identifier,
+    pub iterable: Expression,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+impl ForStatement {
+    pub(crate) fn new(
+        variable: Identifier,
+        iterable: Expression,
+        body: Vec<Statement>,
+        span: Span,
+    ) -> Self {
+        ForStatement {
+            variable,
+            iterable,
+            body,
+            span,
+        }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     LetStatement(LetStatement),
     ReturnStatement(ReturnStatement),
     ExpressionStatement(Expression),
+    WhileStatement(WhileStatement),
+    ForStatement(ForStatement),
+    BreakStatement(Span),
+    ContinueStatement(Span),
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::LetStatement(ls) => ls.span,
+            Statement::ReturnStatement(rs) => rs.span,
+            Statement::ExpressionStatement(expr) => expr.span(),
+            Statement::WhileStatement(ws) => ws.span,
+            Statement::ForStatement(fs) => fs.span,
+            Statement::BreakStatement(span) => *span,
+            Statement::ContinueStatement(span) => *span,
+        }
+    }
 }
 
 impl Display for Statement {
@@ -67,20 +136,37 @@ impl Display for Statement {
             Statement::LetStatement(ls) => write!(f, "let {} = {};", ls.name.value, ls.value),
             Statement::ReturnStatement(rs) => write!(f, "return {};", rs.value),
             Statement::ExpressionStatement(expr) => write!(f, "{};", expr),
+            Statement::WhileStatement(ws) => {
+                write!(f, "while {} {{ ", ws.condition)?;
+                for stmt in ws.body.iter() {
+                    write!(f, "{}", stmt)?
+                }
+                write!(f, " }}")
+            }
+            Statement::ForStatement(fs) => {
+                write!(f, "for {} in {} {{ ", fs.variable, fs.iterable)?;
+                for stmt in fs.body.iter() {
+                    write!(f, "{}", stmt)?
+                }
+                write!(f, " }}")
+            }
+            Statement::BreakStatement(_) => write!(f, "break;"),
+            Statement::ContinueStatement(_) => write!(f, "continue;"),
         }
     }
 }
 
 /** EXPRESSIONS */
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Identifier {
     pub value: String,
+    pub span: Span,
 }
 
 impl Identifier {
-    pub fn new(value: String) -> Self {
-        Identifier { value }
+    pub fn new(value: String, span: Span) -> Self {
+        Identifier { value, span }
     }
 }
 
@@ -90,102 +176,180 @@ impl Display for Identifier {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PrefixExpr {
     pub operator: Token,
     pub expr: Box<Expression>,
+    pub span: Span,
 }
 
 impl PrefixExpr {
-    pub fn new(token: Token, expr: Expression) -> Self {
+    pub fn new(token: Token, expr: Expression, span: Span) -> Self {
         PrefixExpr {
             operator: token,
             expr: Box::new(expr),
+            span,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct InfixExpr {
     pub operator: Token,
     pub left_expr: Box<Expression>,
     pub right_expr: Box<Expression>,
+    pub span: Span,
 }
 
 impl InfixExpr {
-    pub fn new(operator: Token, left_expr: Expression, right_expr: Expression) -> Self {
+    pub fn new(operator: Token, left_expr: Expression, right_expr: Expression, span: Span) -> Self {
         InfixExpr {
             operator,
             left_expr: Box::new(left_expr),
             right_expr: Box::new(right_expr),
+            span,
+        }
+    }
+}
+
+/// `target = value`, or a compound assignment such as `target += value`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AssignExpression {
+    pub operator: Token,
+    pub target: Box<Expression>,
+    pub value: Box<Expression>,
+    pub span: Span,
+}
+
+impl AssignExpression {
+    pub fn new(operator: Token, target: Expression, value: Expression, span: Span) -> Self {
+        AssignExpression {
+            operator,
+            target: Box::new(target),
+            value: Box::new(value),
+            span,
+        }
+    }
+
+    /// Operator combining the current value with `value`, `None` for a plain `=`.
+    pub fn binary_operator(&self) -> Option<Token> {
+        match self.operator {
+            Token::PLUSASSIGN => Some(Token::PLUS),
+            Token::SUBASSIGN => Some(Token::SUB),
+            Token::MULASSIGN => Some(Token::MUL),
+            Token::DIVASSIGN => Some(Token::DIV),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct IfExpression {
     pub condition: Box<Expression>,
     pub consequence: Vec<Statement>,
     pub alternative: Option<Vec<Statement>>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct FnExpression {
     pub parameters: Vec<Identifier>,
     pub body: Vec<Statement>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct CallExpression {
     pub function: Box<Expression>,
     pub arguments: Vec<Expression>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Arrays {
     pub elements: Vec<Expression>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Indexed {
     pub left_expr: Box<Expression>,
     pub index: Box<Expression>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct HashLiteral {
+    pub pairs: Vec<(Expression, Expression)>,
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Identifier(Identifier),
-    Integer(i32),
-    Bool(bool),
-    String(String),
+    Integer(i32, Span),
+    /// Integer literal too large for `Integer`.
+    BigInteger(BigInt, Span),
+    Float(f64, Span),
+    Bool(bool, Span),
+    String(String, Span),
     Prefix(PrefixExpr),
     Infix(InfixExpr),
+    Assign(AssignExpression),
     IfExpression(IfExpression),
     FnExpression(FnExpression),
     CallExpression(CallExpression),
     Arrays(Arrays),
     Indexed(Indexed),
+    HashLiteral(HashLiteral),
 }
 
 impl Expression {
     pub fn new(ident: Identifier) -> Expression {
         Expression::Identifier(ident)
     }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Identifier(ident) => ident.span,
+            Expression::Integer(_, span) => *span,
+            Expression::BigInteger(_, span) => *span,
+            Expression::Float(_, span) => *span,
+            Expression::Bool(_, span) => *span,
+            Expression::String(_, span) => *span,
+            Expression::Prefix(prefix) => prefix.span,
+            Expression::Infix(infix) => infix.span,
+            Expression::Assign(assign) => assign.span,
+            Expression::IfExpression(ifexpr) => ifexpr.span,
+            Expression::FnExpression(func) => func.span,
+            Expression::CallExpression(call) => call.span,
+            Expression::Arrays(array) => array.span,
+            Expression::Indexed(index) => index.span,
+            Expression::HashLiteral(hash) => hash.span,
+        }
+    }
 }
 
 impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Expression::Identifier(ident) => write!(f, "{}", ident),
-            Expression::Integer(int) => write!(f, "{}", int),
-            Expression::Bool(bool) => write!(f, "{}", bool),
-            Expression::String(str) => write!(f, "{}", str),
+            Expression::Integer(int, _) => write!(f, "{}", int),
+            Expression::BigInteger(int, _) => write!(f, "{}", int),
+            Expression::Float(float, _) => write!(f, "{:?}", float),
+            Expression::Bool(bool, _) => write!(f, "{}", bool),
+            Expression::String(str, _) => write!(f, "{}", str),
             Expression::Prefix(prefix) => write!(f, "({}{})", prefix.operator, prefix.expr),
             Expression::Infix(infix) => write!(
                 f,
                 "({} {} {})",
                 infix.left_expr, infix.operator, infix.right_expr
             ),
+            Expression::Assign(assign) => write!(
+                f,
+                "({} {} {})",
+                assign.target, assign.operator, assign.value
+            ),
             Expression::IfExpression(ifexpr) => {
                 write!(f, "if {} {{ ", ifexpr.condition)?;
                 for stmt in ifexpr.consequence.iter() {
@@ -240,6 +404,17 @@ impl Display for Expression {
                 )
             }
             Expression::Indexed(index) => write!(f, "{}[{}]", index.left_expr, index.index),
+            Expression::HashLiteral(hash) => {
+                write!(
+                    f,
+                    "{{{}}}",
+                    hash.pairs
+                        .iter()
+                        .map(|(key, value)| format!("{}: {}", key, value))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
         }
     }
 }
@@ -0,0 +1,558 @@
+//! Binary serialization of parsed programs, so scripts can be reloaded without
+//! lexing and parsing them again.
+//!
+//! A cache starts with `MAGIC`, the little endian `FORMAT_VERSION` and the
+//! checksum of the source it was built from, followed by the statements.
+//! Bump `FORMAT_VERSION` whenever the encoding of the AST changes.
+
+use std::{fs, mem, path::Path};
+
+use super::error::CacheError;
+use crate::{
+    ast::ast::{
+        Arrays, AssignExpression, CallExpression, Expression, FnExpression, ForStatement,
+        HashLiteral, Identifier, IfExpression, Indexed, InfixExpr, LetStatement, PrefixExpr,
+        Program, ReturnStatement, Statement, WhileStatement,
+    },
+    object::bigint::BigInt,
+    token::token::{Position, Span, Token},
+};
+
+pub const MAGIC: [u8; 4] = *b"MKYC";
+pub const FORMAT_VERSION: u16 = 8;
+
+/// Operators that can appear in prefix and infix expressions, encoded by index.
+fn operators() -> [Token; 27] {
+    [
+        Token::PLUS,
+        Token::SUB,
+        Token::MUL,
+        Token::DIV,
+        Token::EQ,
+        Token::NE,
+        Token::GT,
+        Token::LT,
+        Token::BANG,
+        Token::AND,
+        Token::OR,
+        Token::GE,
+        Token::LE,
+        Token::MOD,
+        Token::POW,
+        Token::BITAND,
+        Token::BITOR,
+        Token::BITXOR,
+        Token::SHL,
+        Token::SHR,
+        Token::TILDE,
+        Token::DOTDOT,
+        Token::ASSIGN,
+        Token::PLUSASSIGN,
+        Token::SUBASSIGN,
+        Token::MULASSIGN,
+        Token::DIVASSIGN,
+    ]
+}
+
+const PREFIX_OPERATORS: [Token; 3] = [Token::SUB, Token::BANG, Token::TILDE];
+
+const ASSIGN_OPERATORS: [Token; 5] = [
+    Token::ASSIGN,
+    Token::PLUSASSIGN,
+    Token::SUBASSIGN,
+    Token::MULASSIGN,
+    Token::DIVASSIGN,
+];
+
+fn check(valid: bool, reason: &'static str) -> Result<(), CacheError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(CacheError::InvalidProgram(reason))
+    }
+}
+
+/// 64-bit FNV-1a hash of the source.
+pub fn checksum(source: &str) -> u64 {
+    source.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub fn encode(program: &Program, source: &str) -> Vec<u8> {
+    let mut encoder = Encoder { bytes: Vec::new() };
+    encoder.bytes.extend_from_slice(&MAGIC);
+    encoder
+        .bytes
+        .extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    encoder
+        .bytes
+        .extend_from_slice(&checksum(source).to_le_bytes());
+    encoder.statements(&program.statements);
+    encoder.bytes
+}
+
+/// Decodes a cache, failing with `CacheError::Stale` if it was not built from `source`. The
+/// checksum only catches accidental changes, so the structure the parser guarantees is checked
+/// again.
+pub fn decode(bytes: &[u8], source: &str) -> Result<Program, CacheError> {
+    let mut decoder = Decoder {
+        bytes,
+        position: 0,
+        loop_depth: 0,
+    };
+
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(CacheError::BadMagic);
+    }
+    let version = u16::from_le_bytes(decoder.array()?);
+    if version != FORMAT_VERSION {
+        return Err(CacheError::UnsupportedVersion(version));
+    }
+    if u64::from_le_bytes(decoder.array()?) != checksum(source) {
+        return Err(CacheError::Stale);
+    }
+
+    Ok(Program {
+        statements: decoder.statements()?,
+    })
+}
+
+pub fn load(path: &Path, source: &str) -> Result<Program, CacheError> {
+    decode(&fs::read(path)?, source)
+}
+
+pub fn store(path: &Path, program: &Program, source: &str) -> Result<(), CacheError> {
+    fs::write(path, encode(program, source))?;
+    Ok(())
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn span(&mut self, span: Span) {
+        for position in [span.start, span.end] {
+            self.u32(position.line as u32);
+            self.u32(position.column as u32);
+        }
+    }
+
+    fn operator(&mut self, operator: &Token) {
+        let index = operators()
+            .iter()
+            .position(|op| op == operator)
+            .unwrap_or_else(|| panic!("{operator} is not an operator"));
+        self.u8(index as u8);
+    }
+
+    fn identifier(&mut self, ident: &Identifier) {
+        self.string(&ident.value);
+        self.span(ident.span);
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.len(statements.len());
+        statements.iter().for_each(|stmt| self.statement(stmt));
+    }
+
+    fn expressions(&mut self, expressions: &[Expression]) {
+        self.len(expressions.len());
+        expressions.iter().for_each(|expr| self.expression(expr));
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::LetStatement(let_statement) => {
+                self.u8(0);
+                self.identifier(&let_statement.name);
+                self.expression(&let_statement.value);
+                self.span(let_statement.span);
+            }
+            Statement::ReturnStatement(return_statement) => {
+                self.u8(1);
+                self.expression(&return_statement.value);
+                self.span(return_statement.span);
+            }
+            Statement::ExpressionStatement(expr) => {
+                self.u8(2);
+                self.expression(expr);
+            }
+            Statement::WhileStatement(while_statement) => {
+                self.u8(3);
+                self.expression(&while_statement.condition);
+                self.statements(&while_statement.body);
+                self.span(while_statement.span);
+            }
+            Statement::ForStatement(for_statement) => {
+                self.u8(6);
+                self.identifier(&for_statement.variable);
+                self.expression(&for_statement.iterable);
+                self.statements(&for_statement.body);
+                self.span(for_statement.span);
+            }
+            Statement::BreakStatement(span) => {
+                self.u8(4);
+                self.span(*span);
+            }
+            Statement::ContinueStatement(span) => {
+                self.u8(5);
+                self.span(*span);
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(ident) => {
+                self.u8(0);
+                self.identifier(ident);
+            }
+            Expression::Integer(i, span) => {
+                self.u8(1);
+                self.bytes.extend_from_slice(&i.to_le_bytes());
+                self.span(*span);
+            }
+            Expression::BigInteger(i, span) => {
+                self.u8(12);
+                self.string(&i.to_string());
+                self.span(*span);
+            }
+            Expression::Float(f, span) => {
+                self.u8(13);
+                self.bytes.extend_from_slice(&f.to_le_bytes());
+                self.span(*span);
+            }
+            Expression::Bool(b, span) => {
+                self.u8(2);
+                self.u8(*b as u8);
+                self.span(*span);
+            }
+            Expression::String(s, span) => {
+                self.u8(3);
+                self.string(s);
+                self.span(*span);
+            }
+            Expression::Prefix(prefix) => {
+                self.u8(4);
+                self.operator(&prefix.operator);
+                self.expression(&prefix.expr);
+                self.span(prefix.span);
+            }
+            Expression::Infix(infix) => {
+                self.u8(5);
+                self.operator(&infix.operator);
+                self.expression(&infix.left_expr);
+                self.expression(&infix.right_expr);
+                self.span(infix.span);
+            }
+            Expression::Assign(assign) => {
+                self.u8(14);
+                self.operator(&assign.operator);
+                self.expression(&assign.target);
+                self.expression(&assign.value);
+                self.span(assign.span);
+            }
+            Expression::IfExpression(if_expr) => {
+                self.u8(6);
+                self.expression(&if_expr.condition);
+                self.statements(&if_expr.consequence);
+                match &if_expr.alternative {
+                    Some(alternative) => {
+                        self.u8(1);
+                        self.statements(alternative);
+                    }
+                    None => self.u8(0),
+                }
+                self.span(if_expr.span);
+            }
+            Expression::FnExpression(func) => {
+                self.u8(7);
+                self.len(func.parameters.len());
+                func.parameters
+                    .iter()
+                    .for_each(|param| self.identifier(param));
+                self.statements(&func.body);
+                self.span(func.span);
+            }
+            Expression::CallExpression(call) => {
+                self.u8(8);
+                self.expression(&call.function);
+                self.expressions(&call.arguments);
+                self.span(call.span);
+            }
+            Expression::Arrays(array) => {
+                self.u8(9);
+                self.expressions(&array.elements);
+                self.span(array.span);
+            }
+            Expression::Indexed(indexed) => {
+                self.u8(10);
+                self.expression(&indexed.left_expr);
+                self.expression(&indexed.index);
+                self.span(indexed.span);
+            }
+            Expression::HashLiteral(hash) => {
+                self.u8(11);
+                self.len(hash.pairs.len());
+                hash.pairs.iter().for_each(|(key, value)| {
+                    self.expression(key);
+                    self.expression(value);
+                });
+                self.span(hash.span);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Loops around the statement being decoded, like `Parser::loop_depth`.
+    loop_depth: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], CacheError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(CacheError::Truncated)?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CacheError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, CacheError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CacheError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn len(&mut self) -> Result<usize, CacheError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn string(&mut self) -> Result<String, CacheError> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| CacheError::InvalidString)
+    }
+
+    fn position(&mut self) -> Result<Position, CacheError> {
+        Ok(Position::new(self.u32()? as usize, self.u32()? as usize))
+    }
+
+    fn span(&mut self) -> Result<Span, CacheError> {
+        Ok(Span::new(self.position()?, self.position()?))
+    }
+
+    fn operator(&mut self) -> Result<Token, CacheError> {
+        let tag = self.u8()?;
+        operators()
+            .into_iter()
+            .nth(tag as usize)
+            .ok_or(CacheError::InvalidTag {
+                kind: "operator",
+                tag,
+            })
+    }
+
+    /// Decodes an operator, which must be one of `valid`.
+    fn operator_in(&mut self, valid: &[Token], reason: &'static str) -> Result<Token, CacheError> {
+        let operator = self.operator()?;
+        check(valid.contains(&operator), reason)?;
+        Ok(operator)
+    }
+
+    fn identifier(&mut self) -> Result<Identifier, CacheError> {
+        Ok(Identifier::new(self.string()?, self.span()?))
+    }
+
+    fn statements(&mut self) -> Result<Vec<Statement>, CacheError> {
+        // Lengths come from the file, do not trust them for preallocation.
+        let mut statements = vec![];
+        for _ in 0..self.len()? {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn expressions(&mut self) -> Result<Vec<Expression>, CacheError> {
+        let mut expressions = vec![];
+        for _ in 0..self.len()? {
+            expressions.push(self.expression()?);
+        }
+        Ok(expressions)
+    }
+
+    fn statement(&mut self) -> Result<Statement, CacheError> {
+        Ok(match self.u8()? {
+            0 => {
+                let name = self.identifier()?;
+                let value = self.expression()?;
+                Statement::LetStatement(LetStatement::new(name, value, self.span()?))
+            }
+            1 => {
+                let value = self.expression()?;
+                Statement::ReturnStatement(ReturnStatement::new(value, self.span()?))
+            }
+            2 => Statement::ExpressionStatement(self.expression()?),
+            3 => {
+                let loop_depth = mem::take(&mut self.loop_depth);
+                let condition = self.expression()?;
+                self.loop_depth = loop_depth + 1;
+                let body = self.statements()?;
+                self.loop_depth = loop_depth;
+                Statement::WhileStatement(WhileStatement::new(condition, body, self.span()?))
+            }
+            4 | 5 if self.loop_depth == 0 => {
+                return Err(CacheError::InvalidProgram(
+                    "break or continue outside of a loop",
+                ))
+            }
+            4 => Statement::BreakStatement(self.span()?),
+            5 => Statement::ContinueStatement(self.span()?),
+            6 => {
+                let variable = self.identifier()?;
+                let iterable = self.expression()?;
+                self.loop_depth += 1;
+                let body = self.statements()?;
+                self.loop_depth -= 1;
+                Statement::ForStatement(ForStatement::new(variable, iterable, body, self.span()?))
+            }
+            tag => {
+                return Err(CacheError::InvalidTag {
+                    kind: "statement",
+                    tag,
+                })
+            }
+        })
+    }
+
+    fn expression(&mut self) -> Result<Expression, CacheError> {
+        Ok(match self.u8()? {
+            0 => Expression::Identifier(self.identifier()?),
+            1 => Expression::Integer(i32::from_le_bytes(self.array()?), self.span()?),
+            2 => Expression::Bool(self.u8()? != 0, self.span()?),
+            3 => Expression::String(self.string()?, self.span()?),
+            4 => {
+                let operator = self.operator_in(&PREFIX_OPERATORS, "invalid prefix operator")?;
+                let expr = self.expression()?;
+                Expression::Prefix(PrefixExpr::new(operator, expr, self.span()?))
+            }
+            5 => {
+                let operator = self.operator()?;
+                check(
+                    !matches!(operator, Token::BANG | Token::TILDE)
+                        && !ASSIGN_OPERATORS.contains(&operator),
+                    "invalid infix operator",
+                )?;
+                let left = self.expression()?;
+                let right = self.expression()?;
+                Expression::Infix(InfixExpr::new(operator, left, right, self.span()?))
+            }
+            6 => Expression::IfExpression(IfExpression {
+                condition: Box::new(self.expression()?),
+                consequence: self.statements()?,
+                alternative: match self.u8()? {
+                    0 => None,
+                    _ => Some(self.statements()?),
+                },
+                span: self.span()?,
+            }),
+            7 => {
+                let mut parameters = vec![];
+                for _ in 0..self.len()? {
+                    parameters.push(self.identifier()?);
+                }
+                let loop_depth = mem::take(&mut self.loop_depth);
+                let body = self.statements()?;
+                self.loop_depth = loop_depth;
+                Expression::FnExpression(FnExpression {
+                    parameters,
+                    body,
+                    span: self.span()?,
+                })
+            }
+            8 => Expression::CallExpression(CallExpression {
+                function: Box::new(self.expression()?),
+                arguments: self.expressions()?,
+                span: self.span()?,
+            }),
+            9 => Expression::Arrays(Arrays {
+                elements: self.expressions()?,
+                span: self.span()?,
+            }),
+            10 => Expression::Indexed(Indexed {
+                left_expr: Box::new(self.expression()?),
+                index: Box::new(self.expression()?),
+                span: self.span()?,
+            }),
+            11 => {
+                let mut pairs = vec![];
+                for _ in 0..self.len()? {
+                    pairs.push((self.expression()?, self.expression()?));
+                }
+                Expression::HashLiteral(HashLiteral {
+                    pairs,
+                    span: self.span()?,
+                })
+            }
+            12 => {
+                let digits = self.string()?;
+                let int = BigInt::parse(&digits).ok_or(CacheError::InvalidTag {
+                    kind: "integer",
+                    tag: 12,
+                })?;
+                Expression::BigInteger(int, self.span()?)
+            }
+            13 => {
+                let float = f64::from_le_bytes(self.array()?);
+                check(float.is_finite(), "non-finite float literal")?;
+                Expression::Float(float, self.span()?)
+            }
+            14 => {
+                let operator =
+                    self.operator_in(&ASSIGN_OPERATORS, "invalid assignment operator")?;
+                let target = self.expression()?;
+                check(
+                    matches!(target, Expression::Identifier(_) | Expression::Indexed(_)),
+                    "invalid assignment target",
+                )?;
+                let value = self.expression()?;
+                Expression::Assign(AssignExpression::new(operator, target, value, self.span()?))
+            }
+            tag => {
+                return Err(CacheError::InvalidTag {
+                    kind: "expression",
+                    tag,
+                })
+            }
+        })
+    }
+}
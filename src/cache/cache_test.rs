@@ -0,0 +1,180 @@
+#[cfg(test)]
+pub mod cache_test {
+    use crate::{
+        ast::ast::{
+            AssignExpression, Expression, FnExpression, InfixExpr, PrefixExpr, Program, Statement,
+            WhileStatement,
+        },
+        cache::{
+            cache::{checksum, decode, encode, FORMAT_VERSION, MAGIC},
+            error::CacheError,
+        },
+        evaluator::evaluator::Evaluator,
+        lexer::lexer::Lexer,
+        object::object::Object,
+        parser::parser::Parser,
+        token::token::{Span, Token},
+    };
+
+    const SOURCE: &str = "let fib = fn(n) {
+  if (n < 2) { return n; } else { fib(n - 1) + fib(n - 2) }
+};
+let data = {\"name\": \"monkey\", 1: [true, !false, -3]};
+fib(10) * 2 / 5 - (1 == 1) + (1 != 2) > data[1][2];
+99999999999999999999 * -1;
+1.5e-3 * 2.0;
+1 <= 2 && 3 ** 2 % 4 >= ~1 << 2 ^ 5 & 6 | 7 >> 1 || false;
+while (true) { if (data[1][0]) { break; } else { continue; } }
+for (i in 0..fib(3)) { i; }
+let n = 0; n = 1; n += 2; n -= 3; n *= 4; n /= 5;
+data[1][0] = data[\"name\"]; data[1][2] += 1;";
+
+    fn parse(input: &str) -> Program {
+        Parser::new(Lexer::new(input)).parse_program()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let program = parse(SOURCE);
+        let decoded = decode(&encode(&program, SOURCE), SOURCE).unwrap();
+
+        assert_eq!(decoded.statements, program.statements);
+    }
+
+    #[test]
+    fn decoded_program_evaluates() {
+        let input = "let f = fn(x) { x * 2 }; f(21)";
+        let decoded = decode(&encode(&parse(input), input), input).unwrap();
+
+        assert_eq!(Evaluator::new().eval(decoded), Object::INTEGER(42));
+    }
+
+    #[test]
+    fn checksum_depends_on_source() {
+        assert_eq!(checksum(SOURCE), checksum(SOURCE));
+        assert_ne!(checksum("let a = 1;"), checksum("let a = 2;"));
+    }
+
+    #[test]
+    fn invalid_caches() {
+        let bytes = encode(&parse(SOURCE), SOURCE);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+
+        let mut future_version = bytes.clone();
+        future_version[MAGIC.len()..MAGIC.len() + 2]
+            .copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+        let mut bad_tag = bytes.clone();
+        // Tag of the first statement, right after the header and statement count.
+        bad_tag[MAGIC.len() + 2 + 8 + 4] = 42;
+
+        let input_expctderror = [
+            (bad_magic, SOURCE),
+            (future_version, SOURCE),
+            (bytes.clone(), "let a = 1;"),
+            (bytes[..bytes.len() - 1].to_vec(), SOURCE),
+            (bad_tag, SOURCE),
+        ];
+
+        let errors = input_expctderror
+            .iter()
+            .map(|(bytes, source)| match decode(bytes, source) {
+                Ok(_) => panic!("Should not decode"),
+                Err(err) => err,
+            })
+            .collect::<Vec<CacheError>>();
+
+        assert!(matches!(errors[0], CacheError::BadMagic));
+        assert!(matches!(
+            errors[1],
+            CacheError::UnsupportedVersion(v) if v == FORMAT_VERSION + 1
+        ));
+        assert!(matches!(errors[2], CacheError::Stale));
+        assert!(matches!(errors[3], CacheError::Truncated));
+        assert!(matches!(
+            errors[4],
+            CacheError::InvalidTag {
+                kind: "statement",
+                tag: 42
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_programs_the_parser_would_not_produce() {
+        let span = Span::default();
+        let one = || Expression::Integer(1, span);
+        let in_loop = |statement| {
+            Statement::WhileStatement(WhileStatement::new(
+                Expression::Bool(true, span),
+                vec![statement],
+                span,
+            ))
+        };
+        let function = Expression::FnExpression(FnExpression {
+            parameters: vec![],
+            body: vec![Statement::ContinueStatement(span)],
+            span,
+        });
+
+        let input_expctdreason = [
+            (
+                Statement::BreakStatement(span),
+                "break or continue outside of a loop",
+            ),
+            (
+                in_loop(Statement::ExpressionStatement(function)),
+                "break or continue outside of a loop",
+            ),
+            (
+                Statement::ExpressionStatement(Expression::Prefix(PrefixExpr::new(
+                    Token::MUL,
+                    one(),
+                    span,
+                ))),
+                "invalid prefix operator",
+            ),
+            (
+                Statement::ExpressionStatement(Expression::Infix(InfixExpr::new(
+                    Token::BANG,
+                    one(),
+                    one(),
+                    span,
+                ))),
+                "invalid infix operator",
+            ),
+            (
+                Statement::ExpressionStatement(Expression::Assign(AssignExpression::new(
+                    Token::ASSIGN,
+                    one(),
+                    one(),
+                    span,
+                ))),
+                "invalid assignment target",
+            ),
+            (
+                Statement::ExpressionStatement(Expression::Float(f64::INFINITY, span)),
+                "non-finite float literal",
+            ),
+        ];
+
+        for (statement, expected) in input_expctdreason {
+            let program = Program {
+                statements: vec![statement],
+            };
+            match decode(&encode(&program, SOURCE), SOURCE) {
+                Err(CacheError::InvalidProgram(reason)) => assert_eq!(reason, expected),
+                Err(err) => panic!("Should be invalid, got {err}"),
+                Ok(_) => panic!("Should not decode"),
+            }
+        }
+
+        // Loops still apply to their body.
+        let program = Program {
+            statements: vec![in_loop(Statement::BreakStatement(span))],
+        };
+        assert!(decode(&encode(&program, SOURCE), SOURCE).is_ok());
+    }
+}
@@ -0,0 +1,41 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// The cache was built from a different version of the source.
+    Stale,
+    Truncated,
+    InvalidTag {
+        kind: &'static str,
+        tag: u8,
+    },
+    InvalidString,
+    /// A program the parser would have rejected, so the backends may not handle it.
+    InvalidProgram(&'static str),
+}
+
+impl From<io::Error> for CacheError {
+    fn from(error: io::Error) -> Self {
+        CacheError::Io(error)
+    }
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(error) => write!(f, "{error}"),
+            CacheError::BadMagic => write!(f, "not a program cache"),
+            CacheError::UnsupportedVersion(version) => {
+                write!(f, "unsupported cache format version {version}")
+            }
+            CacheError::Stale => write!(f, "cache does not match the source"),
+            CacheError::Truncated => write!(f, "unexpected end of cache"),
+            CacheError::InvalidTag { kind, tag } => write!(f, "invalid {kind} tag {tag}"),
+            CacheError::InvalidString => write!(f, "invalid UTF-8 string in cache"),
+            CacheError::InvalidProgram(reason) => write!(f, "invalid program in cache: {reason}"),
+        }
+    }
+}
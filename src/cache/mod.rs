@@ -0,0 +1,3 @@
+pub mod cache;
+pub mod cache_test;
+pub mod error;
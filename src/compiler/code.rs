@@ -0,0 +1,145 @@
+use std::{fmt, rc::Rc};
+
+use crate::{object::object::Object, token::token::Span};
+
+/// Operands index into the pools of the `CompiledFunction` holding the instruction,
+/// jump targets are absolute instruction indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Constant(u32),
+    True,
+    False,
+    Null,
+    Pop,
+
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    GreaterEqual,
+    LessEqual,
+    Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Range,
+    Minus,
+    Bang,
+    BitNot,
+
+    Jump(u32),
+    JumpIfFalse(u32),
+
+    /// Records the stack height, which `Break` and `Continue` unwind to before jumping.
+    SetupLoop,
+    PopLoop,
+    Break(u32),
+    Continue(u32),
+    /// Replaces the collection on top of the stack by an iterator kept in the call frame.
+    GetIter,
+    /// Pushes the next value of the innermost iterator, or jumps once it is exhausted.
+    ForIter(u32),
+    PopIter,
+
+    GetName(u32),
+    SetName(u32),
+    /// Updates an existing binding with the value on top of the stack, which it leaves there.
+    AssignName(u32),
+
+    Array(u32),
+    Hash(u32),
+    Index,
+    /// Pops a collection, an index and a value, stores the value and pushes it back.
+    SetIndex,
+    /// Pushes copies of the two values on top of the stack, for compound index assignments.
+    DupPair,
+
+    Closure(u32),
+    /// Number of arguments and name of the callee, used for stack traces.
+    Call(u32, u32),
+    /// Call in tail position: a closure replaces the frame of the caller instead of nesting.
+    TailCall(u32, u32),
+    ReturnValue,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Constant(i) => write!(f, "CONSTANT {i}"),
+            Instruction::True => write!(f, "TRUE"),
+            Instruction::False => write!(f, "FALSE"),
+            Instruction::Null => write!(f, "NULL"),
+            Instruction::Pop => write!(f, "POP"),
+            Instruction::Add => write!(f, "ADD"),
+            Instruction::Sub => write!(f, "SUB"),
+            Instruction::Mul => write!(f, "MUL"),
+            Instruction::Div => write!(f, "DIV"),
+            Instruction::Equal => write!(f, "EQUAL"),
+            Instruction::NotEqual => write!(f, "NOT_EQUAL"),
+            Instruction::GreaterThan => write!(f, "GREATER_THAN"),
+            Instruction::LessThan => write!(f, "LESS_THAN"),
+            Instruction::GreaterEqual => write!(f, "GREATER_EQUAL"),
+            Instruction::LessEqual => write!(f, "LESS_EQUAL"),
+            Instruction::Mod => write!(f, "MOD"),
+            Instruction::Pow => write!(f, "POW"),
+            Instruction::BitAnd => write!(f, "BIT_AND"),
+            Instruction::BitOr => write!(f, "BIT_OR"),
+            Instruction::BitXor => write!(f, "BIT_XOR"),
+            Instruction::ShiftLeft => write!(f, "SHIFT_LEFT"),
+            Instruction::ShiftRight => write!(f, "SHIFT_RIGHT"),
+            Instruction::Range => write!(f, "RANGE"),
+            Instruction::Minus => write!(f, "MINUS"),
+            Instruction::Bang => write!(f, "BANG"),
+            Instruction::BitNot => write!(f, "BIT_NOT"),
+            Instruction::Jump(i) => write!(f, "JUMP {i}"),
+            Instruction::JumpIfFalse(i) => write!(f, "JUMP_IF_FALSE {i}"),
+            Instruction::SetupLoop => write!(f, "SETUP_LOOP"),
+            Instruction::PopLoop => write!(f, "POP_LOOP"),
+            Instruction::Break(i) => write!(f, "BREAK {i}"),
+            Instruction::Continue(i) => write!(f, "CONTINUE {i}"),
+            Instruction::GetIter => write!(f, "GET_ITER"),
+            Instruction::ForIter(i) => write!(f, "FOR_ITER {i}"),
+            Instruction::PopIter => write!(f, "POP_ITER"),
+            Instruction::GetName(i) => write!(f, "GET_NAME {i}"),
+            Instruction::SetName(i) => write!(f, "SET_NAME {i}"),
+            Instruction::AssignName(i) => write!(f, "ASSIGN_NAME {i}"),
+            Instruction::Array(n) => write!(f, "ARRAY {n}"),
+            Instruction::Hash(n) => write!(f, "HASH {n}"),
+            Instruction::Index => write!(f, "INDEX"),
+            Instruction::SetIndex => write!(f, "SET_INDEX"),
+            Instruction::DupPair => write!(f, "DUP_PAIR"),
+            Instruction::Closure(i) => write!(f, "CLOSURE {i}"),
+            Instruction::Call(n, name) => write!(f, "CALL {n} {name}"),
+            Instruction::TailCall(n, name) => write!(f, "TAIL_CALL {n} {name}"),
+            Instruction::ReturnValue => write!(f, "RETURN_VALUE"),
+        }
+    }
+}
+
+/// Unit of compiled code: the program itself or the body of a function literal.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompiledFunction {
+    pub parameters: Vec<String>,
+    pub instructions: Vec<Instruction>,
+    /// Source location of each instruction, reported by runtime errors.
+    pub spans: Vec<Span>,
+    pub constants: Vec<Object>,
+    pub names: Vec<String>,
+    pub functions: Vec<Rc<CompiledFunction>>,
+}
+
+impl fmt::Display for CompiledFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            writeln!(f, "{index:04} {instruction}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,413 @@
+use std::rc::Rc;
+
+use super::code::{CompiledFunction, Instruction};
+use crate::{
+    ast::ast::{
+        AssignExpression, CallExpression, Expression, FnExpression, ForStatement, IfExpression,
+        InfixExpr, Program, Statement, WhileStatement,
+    },
+    object::object::Object,
+    token::token::{Span, Token},
+};
+
+/// Lowers a `Program` to instructions for the `Vm`.
+///
+/// Every statement and block leaves exactly one value on the stack, so that
+/// programs and function bodies evaluate to their last statement like they do
+/// in the `Evaluator`.
+pub struct Compiler {
+    function: CompiledFunction,
+    loops: Vec<Loop>,
+    /// Whether a function body is being compiled, where returned calls are tail calls.
+    in_function: bool,
+}
+
+/// Loop being compiled, innermost last.
+struct Loop {
+    start: u32,
+    /// `Break` instructions to patch once the end of the loop is known.
+    breaks: Vec<usize>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            function: CompiledFunction::default(),
+            loops: Vec::new(),
+            in_function: false,
+        }
+    }
+
+    pub fn compile(mut self, program: &Program) -> CompiledFunction {
+        let end = program
+            .statements
+            .last()
+            .map_or(Span::default(), |stmt| stmt.span());
+        self.compile_block(&program.statements, end, false);
+        self.emit(Instruction::ReturnValue, end);
+        self.function
+    }
+
+    fn compile_function(&mut self, func: &FnExpression) -> u32 {
+        let mut compiler = Compiler::new();
+        compiler.in_function = true;
+        compiler.function.parameters = func
+            .parameters
+            .iter()
+            .map(|param| param.value.clone())
+            .collect();
+        compiler.compile_block(&func.body, func.span, true);
+        compiler.emit(Instruction::ReturnValue, func.span);
+
+        self.function.functions.push(Rc::new(compiler.function));
+        (self.function.functions.len() - 1) as u32
+    }
+
+    /// `tail` is set when the value of the block is returned by the enclosing function.
+    fn compile_block(&mut self, statements: &[Statement], span: Span, tail: bool) {
+        if statements.is_empty() {
+            self.emit(Instruction::Null, span);
+            return;
+        }
+
+        for (index, stmt) in statements.iter().enumerate() {
+            self.compile_statement(stmt, tail && index + 1 == statements.len());
+            if index + 1 < statements.len() {
+                self.emit(Instruction::Pop, stmt.span());
+            }
+        }
+    }
+
+    fn compile_statement(&mut self, stmt: &Statement, tail: bool) {
+        match stmt {
+            Statement::LetStatement(let_statement) => {
+                self.compile_expression(&let_statement.value);
+                let name = self.name(&let_statement.name.value);
+                self.emit(Instruction::SetName(name), let_statement.span);
+            }
+            Statement::ReturnStatement(return_statement) => {
+                self.compile_expression_at(&return_statement.value, self.in_function);
+                self.emit(Instruction::ReturnValue, return_statement.span);
+            }
+            Statement::ExpressionStatement(expr) => self.compile_expression_at(expr, tail),
+            Statement::WhileStatement(while_statement) => {
+                self.compile_while_statement(while_statement)
+            }
+            Statement::ForStatement(for_statement) => self.compile_for_statement(for_statement),
+            Statement::BreakStatement(span) => {
+                let jump = self.emit(Instruction::Break(0), *span);
+                self.loops.last_mut().unwrap().breaks.push(jump);
+            }
+            Statement::ContinueStatement(span) => {
+                let start = self.loops.last().unwrap().start;
+                self.emit(Instruction::Continue(start), *span);
+            }
+        }
+    }
+
+    fn compile_while_statement(&mut self, while_statement: &WhileStatement) {
+        let span = while_statement.span;
+        self.emit(Instruction::SetupLoop, span);
+
+        let start = self.position();
+        self.loops.push(Loop {
+            start,
+            breaks: Vec::new(),
+        });
+        self.compile_expression(&while_statement.condition);
+        let jump_if_false = self.emit(Instruction::JumpIfFalse(0), span);
+        self.compile_block(&while_statement.body, span, false);
+        let compiled = self.loops.pop().unwrap();
+        self.emit(Instruction::Pop, span);
+        self.emit(Instruction::Jump(start), span);
+
+        let end = self.position();
+        self.emit(Instruction::PopLoop, span);
+        self.emit(Instruction::Null, span);
+
+        self.function.instructions[jump_if_false] = Instruction::JumpIfFalse(end);
+        for jump in compiled.breaks {
+            self.function.instructions[jump] = Instruction::Break(end);
+        }
+    }
+
+    fn compile_for_statement(&mut self, for_statement: &ForStatement) {
+        let span = for_statement.span;
+        self.compile_expression(&for_statement.iterable);
+        self.emit(Instruction::GetIter, span);
+        self.emit(Instruction::SetupLoop, span);
+
+        let start = self.position();
+        let for_iter = self.emit(Instruction::ForIter(0), span);
+        let name = self.name(&for_statement.variable.value);
+        self.emit(Instruction::SetName(name), for_statement.variable.span);
+        self.emit(Instruction::Pop, span);
+
+        self.loops.push(Loop {
+            start,
+            breaks: Vec::new(),
+        });
+        self.compile_block(&for_statement.body, span, false);
+        let compiled = self.loops.pop().unwrap();
+        self.emit(Instruction::Pop, span);
+        self.emit(Instruction::Jump(start), span);
+
+        let end = self.position();
+        self.emit(Instruction::PopLoop, span);
+        self.emit(Instruction::PopIter, span);
+        self.emit(Instruction::Null, span);
+
+        self.function.instructions[for_iter] = Instruction::ForIter(end);
+        for jump in compiled.breaks {
+            self.function.instructions[jump] = Instruction::Break(end);
+        }
+    }
+
+    /// Mirrors the tail positions of the `Evaluator`, so both backends report the same frames.
+    fn compile_expression_at(&mut self, expr: &Expression, tail: bool) {
+        match expr {
+            Expression::IfExpression(if_expr) => self.compile_if_expression(if_expr, tail),
+            Expression::CallExpression(call) => self.compile_call_expression(call, tail),
+            expr => self.compile_expression(expr),
+        }
+    }
+
+    fn compile_expression(&mut self, expr: &Expression) {
+        let span = expr.span();
+        match expr {
+            Expression::Identifier(ident) => {
+                let name = self.name(&ident.value);
+                self.emit(Instruction::GetName(name), span);
+            }
+            Expression::Integer(i, _) => {
+                let constant = self.constant(Object::INTEGER(*i));
+                self.emit(Instruction::Constant(constant), span);
+            }
+            Expression::BigInteger(i, _) => {
+                let constant = self.constant(Object::BIGINT(i.clone()));
+                self.emit(Instruction::Constant(constant), span);
+            }
+            Expression::Float(f, _) => {
+                let constant = self.constant(Object::FLOAT(*f));
+                self.emit(Instruction::Constant(constant), span);
+            }
+            Expression::Bool(b, _) => {
+                let instruction = if *b {
+                    Instruction::True
+                } else {
+                    Instruction::False
+                };
+                self.emit(instruction, span);
+            }
+            Expression::String(s, _) => {
+                let constant = self.constant(Object::STRING(s.clone()));
+                self.emit(Instruction::Constant(constant), span);
+            }
+            Expression::Prefix(prefix) => {
+                self.compile_expression(&prefix.expr);
+                let instruction = match prefix.operator {
+                    Token::BANG => Instruction::Bang,
+                    Token::TILDE => Instruction::BitNot,
+                    _ => Instruction::Minus,
+                };
+                self.emit(instruction, span);
+            }
+            Expression::Infix(infix) if matches!(infix.operator, Token::AND | Token::OR) => {
+                self.compile_logical_expression(infix)
+            }
+            Expression::Infix(infix) => {
+                self.compile_expression(&infix.left_expr);
+                self.compile_expression(&infix.right_expr);
+                self.emit(Self::infix_instruction(&infix.operator), span);
+            }
+            Expression::Assign(assign) => self.compile_assign_expression(assign),
+            Expression::IfExpression(if_expr) => self.compile_if_expression(if_expr, false),
+            Expression::FnExpression(func) => {
+                let index = self.compile_function(func);
+                self.emit(Instruction::Closure(index), span);
+            }
+            Expression::CallExpression(call) => self.compile_call_expression(call, false),
+            Expression::Arrays(array) => {
+                array
+                    .elements
+                    .iter()
+                    .for_each(|element| self.compile_expression(element));
+                self.emit(Instruction::Array(array.elements.len() as u32), span);
+            }
+            Expression::Indexed(indexed) => {
+                self.compile_expression(&indexed.left_expr);
+                self.compile_expression(&indexed.index);
+                self.emit(Instruction::Index, span);
+            }
+            Expression::HashLiteral(hash) => {
+                hash.pairs.iter().for_each(|(key, value)| {
+                    self.compile_expression(key);
+                    self.compile_expression(value);
+                });
+                self.emit(Instruction::Hash(hash.pairs.len() as u32), span);
+            }
+        }
+    }
+
+    fn compile_assign_expression(&mut self, assign: &AssignExpression) {
+        let span = assign.span;
+        match assign.target.as_ref() {
+            Expression::Identifier(ident) => {
+                let name = self.name(&ident.value);
+                match assign.binary_operator() {
+                    Some(operator) => {
+                        self.emit(Instruction::GetName(name), span);
+                        self.compile_expression(&assign.value);
+                        self.emit(Self::infix_instruction(&operator), span);
+                    }
+                    None => self.compile_expression(&assign.value),
+                }
+                self.emit(Instruction::AssignName(name), span);
+            }
+            Expression::Indexed(indexed) => {
+                self.compile_expression(&indexed.left_expr);
+                self.compile_expression(&indexed.index);
+                match assign.binary_operator() {
+                    Some(operator) => {
+                        self.emit(Instruction::DupPair, span);
+                        self.emit(Instruction::Index, span);
+                        self.compile_expression(&assign.value);
+                        self.emit(Self::infix_instruction(&operator), span);
+                    }
+                    None => self.compile_expression(&assign.value),
+                }
+                self.emit(Instruction::SetIndex, span);
+            }
+            _ => unreachable!(
+                "the parser only accepts identifiers and indexes as assignment targets"
+            ),
+        }
+    }
+
+    fn infix_instruction(operator: &Token) -> Instruction {
+        match operator {
+            Token::PLUS => Instruction::Add,
+            Token::SUB => Instruction::Sub,
+            Token::MUL => Instruction::Mul,
+            Token::DIV => Instruction::Div,
+            Token::EQ => Instruction::Equal,
+            Token::NE => Instruction::NotEqual,
+            Token::GT => Instruction::GreaterThan,
+            Token::LT => Instruction::LessThan,
+            Token::GE => Instruction::GreaterEqual,
+            Token::LE => Instruction::LessEqual,
+            Token::MOD => Instruction::Mod,
+            Token::POW => Instruction::Pow,
+            Token::BITAND => Instruction::BitAnd,
+            Token::BITOR => Instruction::BitOr,
+            Token::BITXOR => Instruction::BitXor,
+            Token::SHL => Instruction::ShiftLeft,
+            Token::SHR => Instruction::ShiftRight,
+            Token::DOTDOT => Instruction::Range,
+            operator => unreachable!("the parser only produces binary operators, got {operator}"),
+        }
+    }
+
+    fn compile_if_expression(&mut self, if_expr: &IfExpression, tail: bool) {
+        self.compile_expression(&if_expr.condition);
+        let jump_if_false = self.emit(Instruction::JumpIfFalse(0), if_expr.span);
+
+        self.compile_block(&if_expr.consequence, if_expr.span, tail);
+        let jump = self.emit(Instruction::Jump(0), if_expr.span);
+
+        let alternative = self.position();
+        match &if_expr.alternative {
+            Some(statements) => self.compile_block(statements, if_expr.span, tail),
+            None => {
+                self.emit(Instruction::Null, if_expr.span);
+            }
+        }
+        let end = self.position();
+
+        self.function.instructions[jump_if_false] = Instruction::JumpIfFalse(alternative);
+        self.function.instructions[jump] = Instruction::Jump(end);
+    }
+
+    /// Lowers `&&` and `||` to jumps, so the right side only runs when needed.
+    fn compile_logical_expression(&mut self, infix: &InfixExpr) {
+        self.compile_expression(&infix.left_expr);
+        let left_false = self.emit(Instruction::JumpIfFalse(0), infix.span);
+
+        // `a || b` is true as soon as `a` is, `a && b` needs to check `b`.
+        let mut short_circuit = None;
+        if infix.operator == Token::OR {
+            self.emit(Instruction::True, infix.span);
+            short_circuit = Some(self.emit(Instruction::Jump(0), infix.span));
+            let right = self.position();
+            self.function.instructions[left_false] = Instruction::JumpIfFalse(right);
+        }
+
+        self.compile_expression(&infix.right_expr);
+        let right_false = self.emit(Instruction::JumpIfFalse(0), infix.span);
+        self.emit(Instruction::True, infix.span);
+        let jump = self.emit(Instruction::Jump(0), infix.span);
+
+        let falsy = self.position();
+        self.emit(Instruction::False, infix.span);
+        let end = self.position();
+
+        if infix.operator == Token::AND {
+            self.function.instructions[left_false] = Instruction::JumpIfFalse(falsy);
+        }
+        self.function.instructions[right_false] = Instruction::JumpIfFalse(falsy);
+        self.function.instructions[jump] = Instruction::Jump(end);
+        if let Some(short_circuit) = short_circuit {
+            self.function.instructions[short_circuit] = Instruction::Jump(end);
+        }
+    }
+
+    fn compile_call_expression(&mut self, call: &CallExpression, tail: bool) {
+        let name = match call.function.as_ref() {
+            Expression::Identifier(ident) => self.name(&ident.value),
+            _ => self.name("<anonymous>"),
+        };
+
+        self.compile_expression(&call.function);
+        call.arguments
+            .iter()
+            .for_each(|arg| self.compile_expression(arg));
+        let argc = call.arguments.len() as u32;
+        let instruction = if tail {
+            Instruction::TailCall(argc, name)
+        } else {
+            Instruction::Call(argc, name)
+        };
+        self.emit(instruction, call.span);
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.function.instructions.push(instruction);
+        self.function.spans.push(span);
+        self.function.instructions.len() - 1
+    }
+
+    fn position(&self) -> u32 {
+        self.function.instructions.len() as u32
+    }
+
+    fn constant(&mut self, object: Object) -> u32 {
+        self.function.constants.push(object);
+        (self.function.constants.len() - 1) as u32
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        match self.function.names.iter().position(|n| n == name) {
+            Some(index) => index as u32,
+            None => {
+                self.function.names.push(name.to_string());
+                (self.function.names.len() - 1) as u32
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+#[cfg(test)]
+pub mod compiler_test {
+    use crate::{
+        compiler::{
+            code::{CompiledFunction, Instruction::*},
+            compiler::Compiler,
+        },
+        lexer::lexer::Lexer,
+        object::object::Object,
+        parser::parser::Parser,
+    };
+
+    fn compile(input: &str) -> CompiledFunction {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        Compiler::new().compile(&program)
+    }
+
+    #[test]
+    fn compile_expressions() {
+        let input_expctdinstructions = [
+            ("", vec![Null, ReturnValue]),
+            ("1 + 2", vec![Constant(0), Constant(1), Add, ReturnValue]),
+            ("1; 2", vec![Constant(0), Pop, Constant(1), ReturnValue]),
+            (
+                "-1 < 2",
+                vec![Constant(0), Minus, Constant(1), LessThan, ReturnValue],
+            ),
+            (
+                "!true == false",
+                vec![True, Bang, False, Equal, ReturnValue],
+            ),
+            (
+                "[1, 2][0]",
+                vec![
+                    Constant(0),
+                    Constant(1),
+                    Array(2),
+                    Constant(2),
+                    Index,
+                    ReturnValue,
+                ],
+            ),
+            (
+                "{\"a\": 1}",
+                vec![Constant(0), Constant(1), Hash(1), ReturnValue],
+            ),
+        ];
+
+        input_expctdinstructions
+            .iter()
+            .for_each(|(i, v)| assert_eq!(compile(i).instructions, *v, "{i}"));
+    }
+
+    #[test]
+    fn compile_conditionals() {
+        let input_expctdinstructions = [
+            (
+                "if (true) { 10 }; 20",
+                vec![
+                    True,
+                    JumpIfFalse(4),
+                    Constant(0),
+                    Jump(5),
+                    Null,
+                    Pop,
+                    Constant(1),
+                    ReturnValue,
+                ],
+            ),
+            (
+                "if (true) { 10 } else { 20 }",
+                vec![
+                    True,
+                    JumpIfFalse(4),
+                    Constant(0),
+                    Jump(5),
+                    Constant(1),
+                    ReturnValue,
+                ],
+            ),
+            (
+                "true && false",
+                vec![
+                    True,
+                    JumpIfFalse(6),
+                    False,
+                    JumpIfFalse(6),
+                    True,
+                    Jump(7),
+                    False,
+                    ReturnValue,
+                ],
+            ),
+            (
+                "true || false",
+                vec![
+                    True,
+                    JumpIfFalse(4),
+                    True,
+                    Jump(9),
+                    False,
+                    JumpIfFalse(8),
+                    True,
+                    Jump(9),
+                    False,
+                    ReturnValue,
+                ],
+            ),
+        ];
+
+        input_expctdinstructions
+            .iter()
+            .for_each(|(i, v)| assert_eq!(compile(i).instructions, *v, "{i}"));
+    }
+
+    #[test]
+    fn compile_loops() {
+        let compiled = compile("while (true) { if (false) { break } continue; }");
+
+        assert_eq!(
+            compiled.instructions,
+            vec![
+                SetupLoop,
+                True,
+                JumpIfFalse(12),
+                False,
+                JumpIfFalse(7),
+                Break(12),
+                Jump(8),
+                Null,
+                Pop,
+                Continue(1),
+                Pop,
+                Jump(1),
+                PopLoop,
+                Null,
+                ReturnValue,
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_for_loops() {
+        let compiled = compile("for (x in 0..2) { x }");
+
+        assert_eq!(
+            compiled.instructions,
+            vec![
+                Constant(0),
+                Constant(1),
+                Range,
+                GetIter,
+                SetupLoop,
+                ForIter(11),
+                SetName(0),
+                Pop,
+                GetName(0),
+                Pop,
+                Jump(5),
+                PopLoop,
+                PopIter,
+                Null,
+                ReturnValue,
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_bindings() {
+        let compiled = compile("let a = 1; let b = a; b");
+
+        assert_eq!(
+            compiled.instructions,
+            vec![
+                Constant(0),
+                SetName(0),
+                Pop,
+                GetName(0),
+                SetName(1),
+                Pop,
+                GetName(1),
+                ReturnValue
+            ]
+        );
+        assert_eq!(compiled.names, vec!["a", "b"]);
+        assert_eq!(compiled.constants, vec![Object::INTEGER(1)]);
+
+        let compiled = compile("a = 1; a += 2");
+        assert_eq!(
+            compiled.instructions,
+            vec![
+                Constant(0),
+                AssignName(0),
+                Pop,
+                GetName(0),
+                Constant(1),
+                Add,
+                AssignName(0),
+                ReturnValue
+            ]
+        );
+
+        let compiled = compile("a[0] = 1; a[0] += 2");
+        assert_eq!(
+            compiled.instructions,
+            vec![
+                GetName(0),
+                Constant(0),
+                Constant(1),
+                SetIndex,
+                Pop,
+                GetName(0),
+                Constant(2),
+                DupPair,
+                Index,
+                Constant(3),
+                Add,
+                SetIndex,
+                ReturnValue
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_functions() {
+        let compiled = compile("let add = fn(a, b) { return a + b; }; add(1, 2)");
+
+        assert_eq!(
+            compiled.instructions,
+            vec![
+                Closure(0),
+                SetName(0),
+                Pop,
+                GetName(0),
+                Constant(0),
+                Constant(1),
+                Call(2, 0),
+                ReturnValue
+            ]
+        );
+
+        let function = &compiled.functions[0];
+        assert_eq!(function.parameters, vec!["a", "b"]);
+        assert_eq!(
+            function.instructions,
+            vec![GetName(0), GetName(1), Add, ReturnValue, ReturnValue]
+        );
+        assert_eq!(function.spans.len(), function.instructions.len());
+    }
+
+    #[test]
+    fn compile_tail_calls() {
+        let compiled =
+            compile("f(1); fn(n) { let x = f(n); if (x) { f(x) } else { return g(); } }");
+        assert_eq!(compiled.instructions[2], Call(1, 0));
+
+        assert_eq!(
+            compiled.functions[0].instructions,
+            vec![
+                GetName(0),
+                GetName(1),
+                Call(1, 0),
+                SetName(2),
+                Pop,
+                GetName(2),
+                JumpIfFalse(11),
+                GetName(0),
+                GetName(2),
+                TailCall(1, 0),
+                Jump(14),
+                GetName(3),
+                TailCall(0, 3),
+                ReturnValue,
+                ReturnValue
+            ]
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod code;
+pub mod compiler;
+pub mod compiler_test;
@@ -1,47 +1,171 @@
-use std::{cell::RefCell, rc::Rc, string::String, usize};
+use std::{
+    cell::RefCell,
+    mem,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     ast::ast::{
+        Arrays, AssignExpression, CallExpression,
         Expression::{self, *},
-        Identifier, IfExpression, Program,
+        ForStatement, HashLiteral, Identifier, IfExpression, Indexed, InfixExpr, PrefixExpr,
+        Program,
         Statement::{self, *},
+        WhileStatement,
     },
     object::{
         builtin::BuiltinFunction,
         env::Environment,
-        object::{Function, Object},
+        error::{ErrorKind, Frame, RuntimeError},
+        gc::Collector,
+        iterator::ObjectIterator,
+        object::{Function, HashPair, Object, TailCall},
+        ops,
     },
-    token::token::Token,
+    token::token::{Span, Token},
 };
 
-const TRUE: Object = Object::BOOLEAN(true);
-const FALSE: Object = Object::BOOLEAN(false);
 const NULL: Object = Object::NULL;
 
+/// Nested calls allowed by default, tail calls do not count.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// Bytes of native stack an `eval` may use by default, three quarters of the 2MB threads
+/// spawned by `std` get, leaving the rest to the host.
+pub const DEFAULT_STACK_LIMIT: usize = 3 << 19;
+
+pub use crate::object::error::InterruptHandle;
+
 pub struct Evaluator {
     env: Rc<RefCell<Environment>>,
+    frames: Vec<Frame>,
+    /// Calls are evaluated recursively, bounding them keeps the native stack from overflowing.
+    max_depth: usize,
+    /// Bytes of native stack used below `stack_base`, where the running `eval` started, after
+    /// which evaluation fails. Frames vary in size, and expressions nest within calls, so
+    /// `max_depth` alone cannot guarantee this.
+    stack_limit: usize,
+    stack_base: usize,
+    /// Steps, evaluated expressions and loop iterations, allowed by each `eval`.
+    step_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    steps: u64,
+    deadline: Option<Instant>,
+    interrupt: InterruptHandle,
+    /// Bytes of strings, big integers, arrays and hashes allowed to be allocated by each `eval`.
+    memory_limit: Option<usize>,
+    allocated: usize,
+    collector: Collector,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Evaluator {
+    fn drop(&mut self) {
+        // Functions bound in the global environment refer back to it.
+        self.env = Rc::new(RefCell::new(Environment::new()));
+        self.collector.collect();
+    }
 }
 
 impl Evaluator {
     pub fn new() -> Self {
+        let mut collector = Collector::new();
         Evaluator {
-            env: Rc::new(RefCell::new(Environment::new())),
+            env: collector.track(Environment::new()),
+            frames: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            stack_limit: DEFAULT_STACK_LIMIT,
+            stack_base: 0,
+            step_limit: None,
+            time_limit: None,
+            steps: 0,
+            deadline: None,
+            interrupt: InterruptHandle::default(),
+            memory_limit: None,
+            allocated: 0,
+            collector,
         }
     }
 
+    /// Frees the environments, and the values they hold, only kept alive by cycles, returning
+    /// how many. Collections also run on their own as environments are created.
+    pub fn collect_cycles(&mut self) -> usize {
+        self.collector.collect()
+    }
+
+    /// Gives each `eval` an allocation budget, it fails with an `OutOfMemory` error once it
+    /// allocated more than `bytes` for strings, big integers, arrays and hashes in total.
+    /// Memory freed in the meantime is not given back, so this bounds what the program holds,
+    /// but also stops programs holding little that allocate a lot over time.
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Handle interrupting this evaluator, checked at every step.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Sets how many calls may be nested before a `StackOverflow` error. Each one takes a few
+    /// kilobytes of native stack, more in debug builds, so calls may also fail earlier once
+    /// the stack limit is reached.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets how many bytes of native stack each `eval` may use before it fails with a
+    /// `StackOverflow` error, whether calls or nested expressions use them. It must stay below the stack size of the calling thread, minus
+    /// what the host itself uses.
+    pub fn with_stack_limit(mut self, bytes: usize) -> Self {
+        self.stack_limit = bytes;
+        self
+    }
+
+    /// Bounds the work done by each `eval`, which fails with a `BudgetExhausted` error after
+    /// `steps` steps.
+    pub fn with_step_limit(mut self, steps: u64) -> Self {
+        self.step_limit = Some(steps);
+        self
+    }
+
+    /// Bounds the duration of each `eval`, which fails with a `BudgetExhausted` error once
+    /// `limit` has elapsed.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
     pub fn eval(&mut self, node: Program) -> Object {
-        match self.eval_statement_vec(node.statements) {
+        self.stack_base = stack_position();
+        self.steps = 0;
+        self.allocated = 0;
+        self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        match self.eval_statement_vec(&node.statements) {
             Object::RETURN(r) => *r,
             o => o,
         }
     }
 
-    pub fn eval_statement_vec(&mut self, nodes: Vec<Statement>) -> Object {
+    pub fn eval_statement_vec(&mut self, nodes: &[Statement]) -> Object {
+        self.eval_block(nodes, false)
+    }
+
+    /// `tail` is set when the value of the block is returned by the enclosing function.
+    fn eval_block(&mut self, nodes: &[Statement], tail: bool) -> Object {
         let mut last = NULL;
-        for stmt in nodes.iter() {
-            let evaluated = self.eval_statement(stmt.clone());
+        let count = nodes.len();
+        for (index, stmt) in nodes.iter().enumerate() {
+            let evaluated = self.eval_statement(stmt, tail && index + 1 == count);
             match evaluated {
-                Object::RETURN(_) => {
+                Object::RETURN(_) | Object::TAILCALL(_) | Object::BREAK | Object::CONTINUE => {
                     return evaluated;
                 }
                 Object::ERROR(e) => {
@@ -54,110 +178,394 @@ impl Evaluator {
         last
     }
 
-    fn eval_statement(&mut self, node: Statement) -> Object {
+    fn eval_statement(&mut self, node: &Statement, tail: bool) -> Object {
         match node {
             LetStatement(let_statement) => {
-                let evaluated = self.eval_expression(let_statement.value);
-                match evaluated {
-                    Object::ERROR(e) => return Object::ERROR(e),
-                    _ => (),
+                let evaluated = self.eval_expression(&let_statement.value);
+                if self.is_abrupt(&evaluated) {
+                    return evaluated;
                 };
                 self.env
                     .borrow_mut()
-                    .set(let_statement.name.value, evaluated.clone());
+                    .set(let_statement.name.value.clone(), evaluated.clone());
                 evaluated
             }
             ReturnStatement(return_statement) => {
-                Object::RETURN(Box::new(self.eval_expression(return_statement.value)))
+                // Inside a function, the returned expression is always in tail position.
+                let tail = !self.frames.is_empty();
+                match self.eval_expression_at(&return_statement.value, tail) {
+                    evaluated if self.is_abrupt(&evaluated) => evaluated,
+                    evaluated => Object::RETURN(Box::new(evaluated)),
+                }
+            }
+            ExpressionStatement(expression_statement) => {
+                self.eval_expression_at(expression_statement, tail)
+            }
+            Statement::WhileStatement(while_statement) => {
+                self.eval_while_statement(while_statement)
             }
-            ExpressionStatement(expression_statement) => self.eval_expression(expression_statement),
+            Statement::ForStatement(for_statement) => self.eval_for_statement(for_statement),
+            BreakStatement(_) => Object::BREAK,
+            ContinueStatement(_) => Object::CONTINUE,
         }
     }
 
-    fn eval_expression(&mut self, node: Expression) -> Object {
-        match node {
-            Identifier(i) => self.eval_identifier(i),
-            Integer(i) => Object::INTEGER(i),
-            Bool(b) => self.native_bool_to_object(b),
-            String(s) => Object::STRING(s),
-            Prefix(p) => match self.eval_expression(*p.expr) {
-                Object::ERROR(e) => Object::ERROR(e),
-                obj => self.eval_prefix_expression(p.operator, obj),
-            },
-            Infix(i) => {
-                let left_expr = self.eval_expression(*i.left_expr);
-                if let Object::ERROR(_) = left_expr {
-                    return left_expr;
-                };
+    fn eval_while_statement(&mut self, while_statement: &WhileStatement) -> Object {
+        loop {
+            let condition = self.eval_expression(&while_statement.condition);
+            if self.is_abrupt(&condition) {
+                return condition;
+            }
+            if !ops::is_truthy(&condition) {
+                return NULL;
+            }
 
-                let right_expr = self.eval_expression(*i.right_expr);
-                if let Object::ERROR(_) = right_expr {
-                    return right_expr;
-                };
+            match self.eval_statement_vec(&while_statement.body) {
+                Object::BREAK => return NULL,
+                evaluated @ (Object::RETURN(_) | Object::TAILCALL(_) | Object::ERROR(_)) => {
+                    return evaluated
+                }
+                _ => (),
+            }
+        }
+    }
 
-                self.eval_infix_expression(i.operator, left_expr, right_expr)
+    fn eval_for_statement(&mut self, for_statement: &ForStatement) -> Object {
+        let iterable = self.eval_expression(&for_statement.iterable);
+        if self.is_abrupt(&iterable) {
+            return iterable;
+        }
+        let iterator = match ObjectIterator::new(iterable) {
+            Ok(iterator) => iterator,
+            Err(error) => return self.error_at(error, for_statement.span),
+        };
+
+        for value in iterator {
+            // The body may not evaluate any expression.
+            if let Err(error) = self.step() {
+                return self.error_at(error, for_statement.span);
+            }
+            self.env
+                .borrow_mut()
+                .set(for_statement.variable.value.clone(), value);
+
+            match self.eval_statement_vec(&for_statement.body) {
+                Object::BREAK => break,
+                evaluated @ (Object::RETURN(_) | Object::TAILCALL(_) | Object::ERROR(_)) => {
+                    return evaluated
+                }
+                _ => (),
+            }
+        }
+        NULL
+    }
+
+    fn eval_expression(&mut self, node: &Expression) -> Object {
+        self.eval_expression_at(node, false)
+    }
+
+    /// Calls to functions in tail position are not made here: they unwind as a `TAILCALL` to
+    /// the `apply_function` of the enclosing call, so tail recursion uses constant stack.
+    fn eval_expression_at(&mut self, node: &Expression, tail: bool) -> Object {
+        let span = node.span();
+        let evaluated = match self.step() {
+            Ok(()) => self.eval_expression_node(node, tail),
+            Err(error) => Object::ERROR(error),
+        };
+        self.locate(evaluated, span)
+    }
+
+    /// The innermost expression failing is where the error is located.
+    fn locate(&self, evaluated: Object, span: Span) -> Object {
+        match evaluated {
+            Object::ERROR(error) if error.span.is_none() => self.error_at(error, span),
+            evaluated => evaluated,
+        }
+    }
+
+    /// Records `bytes` allocated for `object`, which is replaced by an error past the memory limit.
+    fn allocate(&mut self, bytes: usize, object: Object) -> Object {
+        self.allocated = self.allocated.saturating_add(bytes);
+        match self.memory_limit {
+            Some(limit) if self.allocated > limit => {
+                Object::ERROR(RuntimeError::out_of_memory(limit))
+            }
+            _ => object,
+        }
+    }
+
+    fn error_at(&self, mut error: RuntimeError, span: Span) -> Object {
+        error.span = Some(span);
+        error.stack = self.frames.clone();
+        Object::ERROR(error)
+    }
+
+    /// Counts a step against the budget of the running `eval`, and checks for interrupts and
+    /// for the native stack running out.
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.interrupt.check()?;
+        if self.stack_base.saturating_sub(stack_position()) > self.stack_limit {
+            return Err(RuntimeError::stack_overflow(self.frames.len()));
+        }
+        self.steps += 1;
+        if let Some(limit) = self.step_limit.filter(|limit| self.steps > *limit) {
+            return Err(RuntimeError::steps_exhausted(limit));
+        }
+        // Reading the clock is comparatively slow, so it is only checked every few steps.
+        if let Some(deadline) = self.deadline.filter(|_| self.steps.is_multiple_of(1024)) {
+            if Instant::now() >= deadline {
+                return Err(RuntimeError::time_exhausted(self.time_limit.unwrap()));
             }
-            IfExpression(if_expr) => self.eval_if_expression(if_expr),
+        }
+        Ok(())
+    }
+
+    fn eval_expression_node(&mut self, node: &Expression, tail: bool) -> Object {
+        match node {
+            Identifier(i) => self.eval_identifier(i),
+            Integer(i, _) => Object::INTEGER(*i),
+            BigInteger(i, _) => self.allocate(i.size(), Object::BIGINT(i.clone())),
+            Float(f, _) => Object::FLOAT(*f),
+            Bool(b, _) => ops::native_bool_to_object(*b),
+            String(s, _) => self.allocate(s.len(), Object::STRING(s.clone())),
+            Prefix(p) => self.eval_prefix_expression(p),
+            Assign(a) => self.eval_assign_expression(a),
+            Infix(i) if matches!(i.operator, Token::AND | Token::OR) => {
+                self.eval_logical_expression(i)
+            }
+            Infix(i) => self.eval_infix_operands(i),
+            IfExpression(if_expr) => self.eval_if_expression(if_expr, tail),
             FnExpression(fun) => Object::FUNCTION(Function {
-                parameters: fun.parameters,
-                body: fun.body,
+                parameters: fun.parameters.clone(),
+                body: Rc::new(fun.body.clone()),
                 env: Rc::clone(&self.env),
             }),
-            CallExpression(c) => {
-                let evaluated = self.eval_expression(*c.function);
-                if self.is_error(&evaluated) {
-                    return evaluated;
-                }
+            CallExpression(c) => self.eval_call_expression(c, tail),
+            Arrays(a) => self.eval_array_literal(a),
+            Indexed(i) => self.eval_indexed_expression(i),
+            HashLiteral(h) => self.eval_hash_literal(h),
+        }
+    }
 
-                let args = self.eval_arguments(c.arguments);
+    // The larger arms above get their own function. Debug builds reserve stack for the
+    // temporaries of every arm, and this frame is part of every nested call.
 
-                if args.len() == 1 && self.is_error(&args.first().unwrap()) {
-                    return args.first().unwrap().clone();
-                }
+    fn eval_prefix_expression(&mut self, prefix: &PrefixExpr) -> Object {
+        match self.eval_expression(&prefix.expr) {
+            obj if self.is_abrupt(&obj) => obj,
+            obj => match ops::eval_prefix_expression(&prefix.operator, obj) {
+                Object::BIGINT(i) => self.allocate(i.size(), Object::BIGINT(i)),
+                result => result,
+            },
+        }
+    }
 
-                self.apply_function(&evaluated, args)
-            }
-            Arrays(a) => {
-                let elements = self.eval_arguments(a.elements);
+    fn eval_infix_operands(&mut self, infix: &InfixExpr) -> Object {
+        let left_expr = self.eval_expression(&infix.left_expr);
+        if self.is_abrupt(&left_expr) {
+            return left_expr;
+        };
+
+        let right_expr = self.eval_expression(&infix.right_expr);
+        if self.is_abrupt(&right_expr) {
+            return right_expr;
+        };
+
+        self.eval_infix_expression(&infix.operator, left_expr, right_expr)
+    }
+
+    fn eval_array_literal(&mut self, array: &Arrays) -> Object {
+        let elements = self.eval_arguments(&array.elements);
 
-                if elements.len() == 1 && self.is_error(&elements.first().unwrap()) {
-                    return elements.first().unwrap().clone();
+        if elements.len() == 1 && self.is_abrupt(elements.first().unwrap()) {
+            return elements.first().unwrap().clone();
+        }
+
+        let size = elements.len() * mem::size_of::<Object>();
+        self.allocate(size, Object::array(elements))
+    }
+
+    fn eval_indexed_expression(&mut self, indexed: &Indexed) -> Object {
+        let left = self.eval_expression(&indexed.left_expr);
+        if self.is_abrupt(&left) {
+            return left;
+        }
+        let index = self.eval_expression(&indexed.index);
+        if self.is_abrupt(&index) {
+            return index;
+        }
+        ops::eval_index_expression(left, index)
+    }
+
+    fn eval_assign_expression(&mut self, assign: &AssignExpression) -> Object {
+        let binary_operator = assign.binary_operator();
+        match assign.target.as_ref() {
+            Expression::Identifier(ident) => {
+                let current = match binary_operator {
+                    Some(_) => self.eval_identifier(ident),
+                    None => NULL,
+                };
+                let value = self.eval_assigned_value(binary_operator, current, &assign.value);
+                if self.is_abrupt(&value) {
+                    return value;
                 }
 
-                return Object::ARRAY(elements);
+                if !self.env.borrow_mut().assign(&ident.value, value.clone()) {
+                    return Object::error(
+                        ErrorKind::UnknownIdentifier,
+                        format!("identifier not found: {}", ident.value),
+                    );
+                }
+                value
             }
-            Indexed(i) => {
-                let left = self.eval_expression(*i.left_expr);
-                if self.is_error(&left) {
+            Expression::Indexed(indexed) => {
+                let left = self.eval_expression(&indexed.left_expr);
+                if self.is_abrupt(&left) {
                     return left;
                 }
-                let index = self.eval_expression(*i.index);
-                if self.is_error(&index) {
+                let index = self.eval_expression(&indexed.index);
+                if self.is_abrupt(&index) {
                     return index;
                 }
-                return self.eval_index_expression(left, index);
+
+                let current = match binary_operator {
+                    Some(_) => ops::eval_index_expression(left.clone(), index.clone()),
+                    None => NULL,
+                };
+                let value = self.eval_assigned_value(binary_operator, current, &assign.value);
+                if self.is_abrupt(&value) {
+                    return value;
+                }
+                let entries = ops::hash_len(&left);
+                let assigned = ops::eval_index_assignment(left.clone(), index, value);
+                let size = (ops::hash_len(&left) - entries) * HashPair::SIZE;
+                self.allocate(size, assigned)
+            }
+            _ => unreachable!(
+                "the parser only accepts identifiers and indexes as assignment targets"
+            ),
+        }
+    }
+
+    /// Value stored by an assignment, combined with the `current` one by compound operators.
+    fn eval_assigned_value(
+        &mut self,
+        binary_operator: Option<Token>,
+        current: Object,
+        value: &Expression,
+    ) -> Object {
+        if self.is_abrupt(&current) {
+            return current;
+        }
+        let value = self.eval_expression(value);
+        match binary_operator {
+            Some(operator) if !self.is_abrupt(&value) => {
+                self.eval_infix_expression(&operator, current, value)
             }
+            _ => value,
+        }
+    }
+
+    /// `&&` and `||` only evaluate their right side when the left one does not decide the result.
+    fn eval_logical_expression(&mut self, infix: &InfixExpr) -> Object {
+        let left = self.eval_expression(&infix.left_expr);
+        if self.is_abrupt(&left) {
+            return left;
+        }
+        if ops::is_truthy(&left) == (infix.operator == Token::OR) {
+            return ops::native_bool_to_object(ops::is_truthy(&left));
         }
+
+        let right = self.eval_expression(&infix.right_expr);
+        if self.is_abrupt(&right) {
+            return right;
+        }
+        ops::native_bool_to_object(ops::is_truthy(&right))
     }
 
-    fn eval_arguments(&mut self, args: Vec<Expression>) -> Vec<Object> {
-        args.iter()
-            .map(|e| self.eval_expression(e.clone()))
-            .collect()
+    fn eval_call_expression(&mut self, call: &CallExpression, tail: bool) -> Object {
+        let frame = Frame {
+            function: match call.function.as_ref() {
+                Identifier(ident) => ident.value.clone(),
+                _ => "<anonymous>".to_string(),
+            },
+            call_site: call.span,
+        };
+
+        let evaluated = self.eval_expression(&call.function);
+        if self.is_abrupt(&evaluated) {
+            return evaluated;
+        }
+
+        let args = self.eval_arguments(&call.arguments);
+
+        if args.len() == 1 && self.is_abrupt(args.first().unwrap()) {
+            return args.first().unwrap().clone();
+        }
+
+        match evaluated {
+            Object::FUNCTION(function) if tail => Object::TAILCALL(Box::new(TailCall {
+                function,
+                arguments: args,
+                frame,
+            })),
+            evaluated => self.apply_function(&evaluated, args, frame),
+        }
+    }
+
+    fn eval_infix_expression(&mut self, operator: &Token, left: Object, right: Object) -> Object {
+        match ops::eval_infix_expression(operator, left, right) {
+            Object::STRING(s) => self.allocate(s.len(), Object::STRING(s)),
+            Object::BIGINT(i) => self.allocate(i.size(), Object::BIGINT(i)),
+            result => result,
+        }
     }
 
-    fn apply_function(&mut self, func: &Object, args: Vec<Object>) -> Object {
+    fn eval_arguments(&mut self, args: &[Expression]) -> Vec<Object> {
+        let mut evaluated = vec![];
+        for arg in args {
+            let obj = self.eval_expression(arg);
+            if self.is_abrupt(&obj) {
+                return vec![obj];
+            }
+            evaluated.push(obj);
+        }
+        evaluated
+    }
+
+    fn apply_function(&mut self, func: &Object, args: Vec<Object>, frame: Frame) -> Object {
         match func {
+            Object::FUNCTION(_) if self.frames.len() >= self.max_depth => {
+                Object::ERROR(RuntimeError::stack_overflow(self.frames.len()))
+            }
             Object::FUNCTION(f) => {
-                let extended_env = self.extended_func_env(&f, args);
                 let old_env = Rc::clone(&self.env);
-                self.env = Rc::new(RefCell::new(extended_env));
-                let evaluated = self.eval_statement_vec(f.clone().body);
+                self.frames.push(frame);
+                let (mut function, mut args) = (f.clone(), args);
+                let evaluated = loop {
+                    let env = self.extended_func_env(&function, args);
+                    self.env = self.collector.track(env);
+                    match self.eval_block(&function.body, true) {
+                        // The tail call replaces this one, keeping the native stack flat.
+                        Object::TAILCALL(call) => {
+                            *self.frames.last_mut().unwrap() = call.frame;
+                            (function, args) = (call.function, call.arguments);
+                        }
+                        evaluated => break evaluated,
+                    }
+                };
+                self.frames.pop();
                 self.env = old_env;
                 self.unwrap_return_value(evaluated)
             }
-            Object::BUILTIN(builtin) => builtin.call(args),
-            _ => Object::ERROR(format!("not a function : {}", func.get_type())),
+            Object::BUILTIN(builtin) => {
+                let result = builtin.call(args);
+                self.allocate(builtin.allocated(&result), result)
+            }
+            _ => Object::error(
+                ErrorKind::NotCallable,
+                format!("not a function : {}", func.get_type()),
+            ),
         }
     }
 
@@ -177,150 +585,72 @@ impl Evaluator {
         }
     }
 
-    fn eval_prefix_expression(&mut self, operator: Token, object: Object) -> Object {
-        match operator {
-            Token::BANG => self.eval_bang_expression(object),
-            Token::SUB => self.eval_minus_expression(object),
-            _ => Object::ERROR(format!(
-                "unknown operator: {}{}",
-                operator,
-                object.get_type()
-            )),
+    fn eval_if_expression(&mut self, if_expr: &IfExpression, tail: bool) -> Object {
+        let condition = self.eval_expression(&if_expr.condition);
+        if self.is_abrupt(&condition) {
+            return condition;
         }
-    }
-
-    fn eval_infix_expression(
-        &mut self,
-        operator: Token,
-        object_left: Object,
-        object_right: Object,
-    ) -> Object {
-        match (object_left, object_right) {
-            (Object::INTEGER(a), Object::INTEGER(b)) => {
-                self.eval_integer_infix_expression(operator, a, b)
-            }
-            (Object::BOOLEAN(a), Object::BOOLEAN(b)) => {
-                self.eval_boolean_infix_expression(operator, a, b)
-            }
-            (Object::STRING(a), Object::STRING(b)) => {
-                self.eval_string_infix_expression(operator, a, b)
-            }
-            (s, t) if !PartialEq::eq(&s, &t) => Object::ERROR(format!(
-                "type mismatch: {} {} {}",
-                s.get_type(),
-                operator,
-                t.get_type()
-            )),
-            (left, right) => Object::ERROR(format!(
-                "unknown operator: {} {} {}",
-                left.get_type(),
-                operator,
-                right.get_type()
-            )),
-        }
-    }
-
-    fn eval_boolean_infix_expression(&mut self, operator: Token, a: bool, b: bool) -> Object {
-        match operator {
-            Token::EQ => Object::BOOLEAN(a == b),
-            Token::NE => Object::BOOLEAN(a != b),
-            _ => Object::ERROR(format!("unknown operator: BOOLEAN {operator} BOOLEAN")),
-        }
-    }
-
-    fn eval_integer_infix_expression(&mut self, operator: Token, a: i32, b: i32) -> Object {
-        match operator {
-            Token::PLUS => Object::INTEGER(a + b),
-            Token::SUB => Object::INTEGER(a - b),
-            Token::MUL => Object::INTEGER(a * b),
-            Token::DIV => Object::INTEGER(a / b),
-            Token::EQ => self.native_bool_to_object(a == b),
-            Token::NE => self.native_bool_to_object(a != b),
-            Token::GT => self.native_bool_to_object(a > b),
-            Token::LT => self.native_bool_to_object(a < b),
-            _ => Object::ERROR(format!("unknown operator: INTEGER {} INTEGER", operator)),
-        }
-    }
-
-    fn eval_string_infix_expression(&mut self, operator: Token, a: String, b: String) -> Object {
-        match operator {
-            Token::PLUS => Object::STRING(a + &b),
-            _ => Object::ERROR(format!("unknown operator: STRING {} STRING", operator)),
-        }
-    }
-
-    fn eval_if_expression(&mut self, if_expr: IfExpression) -> Object {
-        let condition = self.eval_expression(*if_expr.condition);
 
-        if self.is_true(condition) == TRUE {
-            self.eval_statement_vec(if_expr.consequence)
-        } else if if_expr.alternative.is_some() {
-            self.eval_statement_vec(if_expr.alternative.unwrap())
+        if ops::is_truthy(&condition) {
+            self.eval_block(&if_expr.consequence, tail)
+        } else if let Some(alternative) = &if_expr.alternative {
+            self.eval_block(alternative, tail)
         } else {
             NULL
         }
     }
 
-    fn eval_bang_expression(&mut self, object: Object) -> Object {
-        match self.is_true(object) {
-            FALSE => TRUE,
-            _ => FALSE,
-        }
-    }
-
-    fn eval_minus_expression(&mut self, object: Object) -> Object {
-        match object {
-            Object::INTEGER(i) => Object::INTEGER(-i),
-            obj => Object::ERROR(format!("unknown operator: -{}", obj.get_type())),
-        }
-    }
-
-    fn eval_identifier(&mut self, ident: Identifier) -> Object {
-        match self.env.borrow().get(&ident.value) {
-            Some(result) => return result,
-            None => (),
+    fn eval_identifier(&mut self, ident: &Identifier) -> Object {
+        if let Some(result) = self.env.borrow().get(&ident.value) {
+            return result;
         }
 
         match BuiltinFunction::get_builtin(&ident.value) {
             Some(result) => result,
-            None => return Object::ERROR(format!("identifier not found: {}", ident.value)),
+            None => Object::error(
+                ErrorKind::UnknownIdentifier,
+                format!("identifier not found: {}", ident.value),
+            ),
         }
     }
 
-    fn eval_index_expression(&mut self, left: Object, index: Object) -> Object {
-        match (left, index) {
-            (Object::ARRAY(a), Object::INTEGER(i)) => self.eval_array_index_expression(a, i),
-            (obj, _) => Object::ERROR(format!("index operator not supported for {obj}.")),
-        }
-    }
+    fn eval_hash_literal(&mut self, hash: &HashLiteral) -> Object {
+        let mut pairs = vec![];
 
-    fn eval_array_index_expression(&mut self, left: Vec<Object>, index: i32) -> Object {
-        if index < 0 || index > (left.len() - 1).try_into().unwrap() {
-            return Object::NULL;
-        } else {
-            left.get(index as usize).unwrap().clone()
-        }
-    }
+        for (key_expr, value_expr) in &hash.pairs {
+            let key = self.eval_expression(key_expr);
+            if self.is_abrupt(&key) {
+                return key;
+            }
 
-    fn is_true(&mut self, object: Object) -> Object {
-        match object {
-            FALSE | Object::NULL | Object::INTEGER(0) => FALSE,
-            _ => TRUE,
-        }
-    }
+            let value = self.eval_expression(value_expr);
+            if self.is_abrupt(&value) {
+                return value;
+            }
 
-    fn is_error(&mut self, object: &Object) -> bool {
-        match object {
-            Object::ERROR(_) => true,
-            _ => false,
+            pairs.push((key, value));
         }
+
+        let size = pairs.len() * HashPair::SIZE;
+        self.allocate(size, ops::eval_hash_literal(pairs))
     }
 
-    fn native_bool_to_object(&mut self, b: bool) -> Object {
-        if b {
-            TRUE
-        } else {
-            FALSE
-        }
+    /// Errors, and the values produced by `return`, tail calls, `break` and `continue`, stop
+    /// the evaluation of the enclosing expressions.
+    fn is_abrupt(&mut self, object: &Object) -> bool {
+        matches!(
+            object,
+            Object::ERROR(_)
+                | Object::RETURN(_)
+                | Object::TAILCALL(_)
+                | Object::BREAK
+                | Object::CONTINUE
+        )
     }
 }
+
+/// Address of the top of the native stack, which grows downwards.
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
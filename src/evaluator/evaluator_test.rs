@@ -1,15 +1,54 @@
 #[cfg(test)]
 pub mod evaluator_test {
+    use std::{
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
     use crate::{
-        evaluator::evaluator::Evaluator, lexer::lexer::Lexer, object::object::Object,
+        compiler::compiler::Compiler,
+        evaluator::evaluator::Evaluator,
+        lexer::lexer::Lexer,
+        object::{
+            error::{ErrorKind, RuntimeError},
+            object::Object,
+        },
         parser::parser::Parser,
+        token::token::Position,
+        vm::vm::{self, Vm},
     };
 
+    /// Evaluates `input` with both the `Evaluator` and the `Vm`, checking they agree.
     fn test_eval(input: &str) -> Object {
-        let lexer = Lexer::new(input);
-        let mut parser = Parser::new(lexer);
-        let mut evaluator = Evaluator::new();
-        evaluator.eval(parser.parse_program())
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        let executed = Vm::new().run(Compiler::new().compile(&program));
+        let evaluated = Evaluator::new().eval(program);
+
+        match (&evaluated, &executed) {
+            (Object::FUNCTION(_), Object::CLOSURE(_)) => (),
+            (Object::ERROR(a), Object::ERROR(b)) => {
+                assert_eq!((a.kind, &a.message), (b.kind, &b.message), "{input}");
+                assert_eq!(
+                    a.span.map(|span| span.start),
+                    b.span.map(|span| span.start),
+                    "{input}"
+                );
+                let trace = |e: &RuntimeError| {
+                    e.stack
+                        .iter()
+                        .map(|frame| (frame.function.clone(), frame.call_site.start))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(trace(a), trace(b), "{input}");
+            }
+            // Collections may contain themselves, unlike `PartialEq` their display handles cycles.
+            (Object::ARRAY(_) | Object::HASH(_), _) => {
+                assert_eq!(evaluated.to_string(), executed.to_string(), "{input}")
+            }
+            _ => assert_eq!(evaluated, executed, "{input}"),
+        }
+
+        evaluated
     }
 
     fn eval_integer_object(obj: Object, expected_value: i32) {
@@ -20,6 +59,13 @@ pub mod evaluator_test {
         assert_eq!(obj, Object::BOOLEAN(expected_value));
     }
 
+    fn eval_error_object(obj: Object, expected_message: &str) {
+        match obj {
+            Object::ERROR(e) => assert_eq!(e.message, expected_message),
+            obj => panic!("Should be an error, got {obj}"),
+        }
+    }
+
     #[test]
     fn eval_integer_expression() {
         let input_expctdvalue = vec![
@@ -94,6 +140,357 @@ pub mod evaluator_test {
             .for_each(|(i, v)| eval_boolean_object(test_eval(i), *v));
     }
 
+    #[test]
+    fn eval_logical_operators() {
+        let input_expctdvalue = [
+            ("true && true", true),
+            ("true && false", false),
+            ("false || true", true),
+            ("false || false", false),
+            ("1 && \"a\"", true),
+            ("0 || 0.0", false),
+            ("[] && {}", true),
+            ("false && undefined", false),
+            ("true || 1 / 0", true),
+            ("1 < 2 && 3 > 2 || false", true),
+            ("false || true && false", false),
+            ("let f = fn(n) { n > 0 && f(n - 1) || n == 0 }; f(3)", true),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| eval_boolean_object(test_eval(i), *v));
+
+        eval_error_object(
+            test_eval("true && undefined"),
+            "identifier not found: undefined",
+        );
+        eval_error_object(test_eval("false || 1 / 0"), "division by zero: 1 / 0");
+    }
+
+    #[test]
+    fn comparison_operators() {
+        let input_expctdvalue = [
+            ("1 <= 2", true),
+            ("2 <= 2", true),
+            ("3 <= 2", false),
+            ("1 >= 2", false),
+            ("2 >= 2", true),
+            ("2147483648 >= 2147483647", true),
+            ("-2147483649 <= -2147483648", true),
+            ("1.5 >= 1", true),
+            ("1 <= 0.5", false),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| eval_boolean_object(test_eval(i), *v));
+    }
+
+    #[test]
+    fn arithmetic_and_bitwise_operators() {
+        let input_expctdvalue = [
+            ("7 % 3", "1"),
+            ("-7 % 3", "-1"),
+            ("7 % -3", "1"),
+            ("-7 % -3", "-1"),
+            ("-2147483648 % -1", "0"),
+            ("10000000000 % 3", "1"),
+            ("-10000000000 % 7", "-4"),
+            ("2 ** 10", "1024"),
+            ("2 ** 3 ** 2", "512"),
+            ("-2 ** 2", "-4"),
+            ("(-2) ** 3", "-8"),
+            ("0 ** 0", "1"),
+            ("2 ** 31", "2147483648"),
+            ("3 ** 40", "12157665459056928801"),
+            ("2 ** 0.5 * 2 ** 0.5 > 1.99", "true"),
+            ("4.0 ** 0.5", "2.0"),
+            ("7.5 % 2", "1.5"),
+            ("12 & 10", "8"),
+            ("12 | 10", "14"),
+            ("12 ^ 10", "6"),
+            ("~5", "-6"),
+            ("~-1", "0"),
+            ("-12 & 10", "0"),
+            ("-12 | 10", "-2"),
+            ("1 << 4", "16"),
+            ("1 << 31", "2147483648"),
+            ("1 << 64", "18446744073709551616"),
+            ("-1 << 31", "-2147483648"),
+            ("256 >> 4", "16"),
+            ("-17 >> 2", "-5"),
+            ("-1 >> 100", "-1"),
+            ("1 >> 100", "0"),
+            ("(1 << 100) >> 99", "2"),
+            ("-(1 << 100) >> 99", "-2"),
+            ("-(1 << 100) - 1 >> 99", "-3"),
+            ("(1 << 64) - 1 & (1 << 40)", "1099511627776"),
+            ("-(1 << 64) | 1", "-18446744073709551615"),
+            ("(1 << 64) ^ -1", "-18446744073709551617"),
+            ("~(1 << 64)", "-18446744073709551617"),
+            ("1 + 2 * 3 % 4 << 1", "6"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i).to_string(), *v, "{i}"));
+    }
+
+    #[test]
+    fn operator_errors() {
+        let input_expctdmessage = [
+            ("1 % 0", "division by zero: 1 % 0"),
+            ("10000000000 % 0", "division by zero: 10000000000 % 0"),
+            ("2 ** -1", "negative exponent: 2 ** -1"),
+            ("1 << -1", "negative shift count: 1 << -1"),
+            ("1 >> -1", "negative shift count: 1 >> -1"),
+            ("2 ** 10000000", "integer too large: 2 ** 10000000"),
+            ("1 << 2000000", "integer too large: 1 << 2000000"),
+            (
+                "(1 << 600000) * (1 << 600000)",
+                "integer too large: <600001-bit integer> * <600001-bit integer>",
+            ),
+            ("1.5 & 1", "unknown operator: FLOAT & FLOAT"),
+            ("~1.5", "unknown operator: ~FLOAT"),
+            ("true <= false", "unknown operator: BOOLEAN <= BOOLEAN"),
+            ("\"a\" % \"b\"", "unknown operator: STRING % STRING"),
+        ];
+
+        input_expctdmessage
+            .iter()
+            .for_each(|(i, m)| eval_error_object(test_eval(i), m));
+    }
+
+    #[test]
+    fn while_loops() {
+        let input_expctdvalue = [
+            ("let i = 0; while (i < 0) { 1 }", "null"),
+            (
+                "let f = fn(n, acc) { while (n > 0) { return f(n - 1, acc + n) } acc }; f(10, 0)",
+                "55",
+            ),
+            (
+                "let f = fn() { let i = 0; while (true) { let i = i + 1; if (i > 4) { return i } } }; f()",
+                "5",
+            ),
+            (
+                "let r = []; let xs = [1, 2, 3, 4, 5, 6]; let i = 0; \
+                 while (i < len(xs)) { let x = xs[i]; let i = i + 1; \
+                 if (x % 2 == 0) { continue; } if (x > 4) { break; } let r = push(r, x); } r",
+                "[1, 3]",
+            ),
+            (
+                "let n = 0; let i = 0; while (i < 3) { let i = i + 1; let j = 0; \
+                 while (true) { let j = j + 1; if (j > i) { break } let n = n + 1; } } n",
+                "6",
+            ),
+            (
+                "let i = 0; while (i < 5) { let i = i + 1; let x = 1 + if (i == 3) { break } else { i }; } i",
+                "3",
+            ),
+            (
+                "let i = 0; while (i < 100000) { let i = i + 1; } i",
+                "100000",
+            ),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i).to_string(), *v, "{i}"));
+
+        eval_error_object(
+            test_eval("let i = 0; while (i < 3) { let i = i + 1; if (i == 2) { i + true } }"),
+            "type mismatch: INTEGER + BOOLEAN",
+        );
+    }
+
+    #[test]
+    fn for_loops() {
+        let input_expctdvalue = [
+            ("let s = 0; for (x in [1, 2, 3]) { let s = s + x; } s", "6"),
+            ("let s = \"\"; for (c in \"héllo\") { let s = c + s; } s", "olléh"),
+            (
+                "let s = []; for (k in {\"b\": 1, \"a\": 2, 3: 3}) { let s = push(s, k); } s",
+                "[3, a, b]",
+            ),
+            ("let s = 0; for (i in 0..5) { let s = s + i; } s", "10"),
+            ("let s = 0; for (i in 5..0) { let s = s + i; } s", "0"),
+            ("for (x in []) { x }", "null"),
+            ("0..5", "0..5"),
+            ("let n = 3; -n..n * 2", "-3..6"),
+            ("let r = 1..3; let s = 0; for (i in r) { for (j in r) { let s = s + i * j; } } s", "9"),
+            (
+                "let s = 0; for (i in 0..2147483647) { if (i == 3) { continue } if (i > 5) { break } let s = s + i; } s",
+                "12",
+            ),
+            (
+                "let find = fn(xs, y) { for (x in xs) { if (x == y) { return true } } false }; \
+                 [find([1, 2], 2), find([1, 2], 3)]",
+                "[true, false]",
+            ),
+            (
+                "let s = 0; for (i in 0..3) { let j = 0; while (true) { let j = j + 1; if (j > i) { break } let s = s + 1; } } s",
+                "3",
+            ),
+            ("let x = 0; for (x in 0..100000) { } x", "99999"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i).to_string(), *v, "{i}"));
+
+        let input_expctdmessage = [
+            ("for (x in 5) { x }", "not iterable: INTEGER"),
+            ("for (x in fn() {}) { x }", "not iterable: FUNCTION"),
+            ("1..true", "range bounds must be INTEGER: INTEGER..BOOLEAN"),
+            ("0..10000000000", "range bound too large: 0..10000000000"),
+            ("(0..1) + 1", "type mismatch: RANGE + INTEGER"),
+            (
+                "for (i in 0..3) { if (i == 2) { i + true } }",
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+        ];
+
+        input_expctdmessage
+            .iter()
+            .for_each(|(i, m)| eval_error_object(test_eval(i), m));
+    }
+
+    #[test]
+    fn assignments() {
+        let input_expctdvalue = [
+            ("let x = 1; x = 2; x", "2"),
+            ("let x = 1; x = x + 1", "2"),
+            ("let x = 1; let y = 2; x = y = 3; [x, y]", "[3, 3]"),
+            ("let x = 10; x += 5; x -= 3; x *= 2; x /= 4; x", "6"),
+            ("let s = \"a\"; s += \"b\"; s", "ab"),
+            ("let x = 2147483647; x += 1; x", "2147483648"),
+            (
+                "let counter = fn() { let n = 0; fn() { n += 1 } }; let c = counter(); c(); c(); c()",
+                "3",
+            ),
+            (
+                "let total = 0; let add = fn(x) { total = total + x; }; add(2); add(3); total",
+                "5",
+            ),
+            ("let x = 1; let f = fn() { let x = 5; x = 6; x }; [f(), x]", "[6, 1]"),
+            ("let n = 0; for (i in 1..5) { n += i; } n", "10"),
+            ("let i = 0; while (i < 10) { i += 1; } i", "10"),
+            ("let x = 0; if (x = 1) { x } else { -1 }", "1"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i).to_string(), *v, "{i}"));
+
+        let input_expctdmessage = [
+            ("x = 1", "identifier not found: x"),
+            ("x += 1", "identifier not found: x"),
+            ("let f = fn() { y = 1 }; f()", "identifier not found: y"),
+            ("let x = 1; x += true", "type mismatch: INTEGER + BOOLEAN"),
+            ("let x = 1; x /= 0", "division by zero: 1 / 0"),
+            ("len += 1", "type mismatch: BUILTIN + INTEGER"),
+        ];
+
+        input_expctdmessage
+            .iter()
+            .for_each(|(i, m)| eval_error_object(test_eval(i), m));
+    }
+
+    #[test]
+    fn index_assignments() {
+        let input_expctdvalue = [
+            ("let a = [1, 2, 3]; a[1] = 5; a", "[1, 5, 3]"),
+            ("let a = [1, 2, 3]; a[0] = a[2] = 7", "7"),
+            ("let a = [1]; let b = a; b[0] = 2; a[0]", "2"),
+            ("let a = [1, 2]; a[1] += 10; a[1] *= 2; a", "[1, 24]"),
+            (
+                "let h = {}; h[\"a\"] = 1; h[true] = 2; h[\"a\"] += 5; h",
+                "{true: 2, a: 6}",
+            ),
+            (
+                "let m = [[0, 0], [0, 0]]; m[1][0] = 3; m",
+                "[[0, 0], [3, 0]]",
+            ),
+            (
+                "let a = []; let f = fn(x) { a = push(a, x) }; f(1); f(2); a",
+                "[1, 2]",
+            ),
+            (
+                "let a = [1]; let b = push(a, 2); b[0] = 9; [a, b]",
+                "[[1], [9, 2]]",
+            ),
+            ("let a = [1, 2]; let t = tail(a); t[0] = 5; a", "[1, 2]"),
+            (
+                "let a = [1, 2]; for (x in a) { a = push(a, x); } a",
+                "[1, 2, 1, 2]",
+            ),
+            (
+                "let a = [1, 2, 3]; let t = tail(tail(a)); a[2] = 4; [a, t]",
+                "[[1, 2, 4], [3]]",
+            ),
+            ("let a = [1]; a[0] = a; a", "[[...]]"),
+            ("let h = {}; h[1] = [h]; h", "{1: [{...}]}"),
+            (
+                "let sort = fn(a) {
+                   let i = 1;
+                   while (i < len(a)) {
+                     let j = i;
+                     while (j > 0 && a[j - 1] > a[j]) {
+                       let t = a[j]; a[j] = a[j - 1]; a[j - 1] = t;
+                       j -= 1;
+                     }
+                     i += 1;
+                   }
+                 };
+                 let a = [5, 2, 4, 1, 3]; sort(a); a",
+                "[1, 2, 3, 4, 5]",
+            ),
+            (
+                "let memo = {};
+                 let fib = fn(n) {
+                   if (n < 2) { return n; }
+                   if (memo[n]) { return memo[n]; }
+                   memo[n] = fib(n - 1) + fib(n - 2)
+                 };
+                 fib(60)",
+                "1548008755920",
+            ),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i).to_string(), *v, "{i}"));
+
+        let input_expctdmessage = [
+            ("let a = [1]; a[1] = 2", "index out of bounds: 1 (length 1)"),
+            (
+                "let a = [1]; a[-1] = 2",
+                "index out of bounds: -1 (length 1)",
+            ),
+            (
+                "let a = []; a[99999999999] = 2",
+                "index out of bounds: 99999999999 (length 0)",
+            ),
+            ("let h = {}; h[[]] = 1", "unusable as hash key: ARRAY"),
+            (
+                "let s = \"ab\"; s[0] = \"c\"",
+                "index assignment not supported for STRING",
+            ),
+            (
+                "let a = [1]; a[0] += true",
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            ("let h = {}; h[1] += 1", "type mismatch: NULL + INTEGER"),
+            ("b[0] = 1", "identifier not found: b"),
+        ];
+
+        input_expctdmessage
+            .iter()
+            .for_each(|(i, m)| eval_error_object(test_eval(i), m));
+    }
+
     #[test]
     fn eval_string() {
         let input_expctdvalue = vec![("\"hello world\"", "hello world")];
@@ -103,6 +500,47 @@ pub mod evaluator_test {
             .for_each(|(i, v)| assert_eq!(test_eval(i), Object::STRING(v.to_string())));
     }
 
+    #[test]
+    fn eval_string_escapes() {
+        let input_expctdvalue = [
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#""a\tb\nc""#, "a\tb\nc"),
+            (r#""\u{263A}" + "\\""#, "\u{263A}\\"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i), Object::STRING(v.to_string())));
+    }
+
+    #[test]
+    fn eval_unicode() {
+        let input_expctdvalue = [
+            (
+                "let café = \"naïve\"; café + \" ☕\"",
+                Object::STRING("naïve ☕".to_string()),
+            ),
+            ("let π2 = 6; π2 / 2", Object::INTEGER(3)),
+            ("len(\"日本語\")", Object::INTEGER(3)),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i), *v));
+    }
+
+    #[test]
+    fn eval_comments() {
+        let input = "// doubles its argument
+let double = fn(x) {
+    x * 2 // not x / 2
+};
+/* double(1) /* nested */ */
+double(21)";
+
+        eval_integer_object(test_eval(input), 42);
+    }
+
     #[test]
     fn eval_string_concatenation() {
         let input_expctdvalue = vec![("\"hello\" + \" \" + \"world\"", "hello world")];
@@ -176,7 +614,7 @@ pub mod evaluator_test {
 
         input_expctdvalue
             .iter()
-            .for_each(|(i, v)| assert_eq!(test_eval(i), Object::ERROR(v.to_string())));
+            .for_each(|(i, v)| eval_error_object(test_eval(i), v));
     }
 
     #[test]
@@ -289,7 +727,7 @@ addTwo(2);";
 
         input_expctdvalue
             .iter()
-            .for_each(|(i, v)| assert_eq!(test_eval(i), Object::ERROR(v.to_string())));
+            .for_each(|(i, v)| eval_error_object(test_eval(i), v));
     }
 
     #[test]
@@ -301,7 +739,7 @@ addTwo(2);";
 
         input_expctdvalue
             .iter()
-            .for_each(|(i, v)| assert_eq!(test_eval(i), Object::ARRAY(v.to_vec())));
+            .for_each(|(i, v)| assert_eq!(test_eval(i), Object::array(v.to_vec())));
     }
 
     #[test]
@@ -323,5 +761,705 @@ addTwo(2);";
         input_expctdvalue
             .iter()
             .for_each(|(i, v)| assert_eq!(test_eval(i), Object::INTEGER(*v)));
+
+        ["[][0]", "[1, 2, 3][3]", "[1, 2, 3][-1]"]
+            .iter()
+            .for_each(|i| assert_eq!(test_eval(i), Object::NULL));
+    }
+
+    #[test]
+    fn hash_literals() {
+        let input = "let two = \"two\";
+{
+    \"one\": 10 - 9,
+    two: 1 + 1,
+    \"thr\" + \"ee\": 6 / 2,
+    4: 4,
+    true: 5,
+    false: 6
+}";
+
+        let expected = [
+            (Object::STRING("one".to_string()), Object::INTEGER(1)),
+            (Object::STRING("two".to_string()), Object::INTEGER(2)),
+            (Object::STRING("three".to_string()), Object::INTEGER(3)),
+            (Object::INTEGER(4), Object::INTEGER(4)),
+            (Object::BOOLEAN(true), Object::INTEGER(5)),
+            (Object::BOOLEAN(false), Object::INTEGER(6)),
+        ];
+
+        match test_eval(input) {
+            Object::HASH(h) => {
+                let h = h.borrow();
+                assert_eq!(h.len(), expected.len());
+                expected.iter().for_each(|(key, value)| {
+                    let pair = h.get(&key.hash_key().unwrap()).unwrap();
+                    assert_eq!(pair.key, *key);
+                    assert_eq!(pair.value, *value);
+                });
+            }
+            obj => panic!("Should be a hash, got {obj}"),
+        }
+    }
+
+    #[test]
+    fn indexed_hashes() {
+        let input_expctdvalue = [
+            ("{\"foo\": 5}[\"foo\"]", Object::INTEGER(5)),
+            ("{\"foo\": 5}[\"bar\"]", Object::NULL),
+            ("let key = \"foo\"; {\"foo\": 5}[key]", Object::INTEGER(5)),
+            ("{}[\"foo\"]", Object::NULL),
+            ("{5: 5}[5]", Object::INTEGER(5)),
+            ("{true: 5}[true]", Object::INTEGER(5)),
+            ("{false: 5}[false]", Object::INTEGER(5)),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i), *v));
+    }
+
+    #[test]
+    fn hash_key_errors() {
+        let input_expctdvalue = [
+            (
+                "{\"name\": \"Monkey\"}[fn(x) { x }];",
+                "unusable as hash key: FUNCTION",
+            ),
+            ("{[1]: 2}", "unusable as hash key: ARRAY"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| eval_error_object(test_eval(i), v));
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let input_expctdvalue = [
+            ("1 / 0", "division by zero: 1 / 0"),
+            ("let f = fn(x) { 10 / x }; f(0)", "division by zero: 10 / 0"),
+            ("99999999999 / 0", "division by zero: 99999999999 / 0"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| eval_error_object(test_eval(i), v));
+
+        eval_integer_object(test_eval("2147483646 + 1"), 2147483647);
+        eval_integer_object(test_eval("-2147483647 - 1"), -2147483648);
+    }
+
+    #[test]
+    fn big_integers() {
+        let input_expctdvalue = [
+            ("2147483647 + 1", "2147483648"),
+            ("-2147483647 - 2", "-2147483649"),
+            ("65536 * 65536", "4294967296"),
+            ("-(-2147483647 - 1)", "2147483648"),
+            ("(-2147483647 - 1) / -1", "2147483648"),
+            ("99999999999999999999 + 1", "100000000000000000000"),
+            (
+                "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(25)",
+                "15511210043330985984000000",
+            ),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| match test_eval(i) {
+                Object::BIGINT(b) => assert_eq!(b.to_string(), *v),
+                obj => panic!("Should be a big integer, got {obj}"),
+            });
+
+        let input_expctdvalue = [
+            ("2147483648 - 1", Object::INTEGER(2147483647)),
+            ("-2147483648", Object::INTEGER(-2147483648)),
+            ("4294967296 / 4294967296", Object::INTEGER(1)),
+            ("4294967296 > 5", Object::BOOLEAN(true)),
+            ("-4294967296 < 5", Object::BOOLEAN(true)),
+            ("4294967296 == 2 * 2147483648", Object::BOOLEAN(true)),
+            ("4294967296 == 1", Object::BOOLEAN(false)),
+            ("{4294967296: 1}[65536 * 65536]", Object::INTEGER(1)),
+            ("[1][4294967296]", Object::NULL),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i), *v));
+
+        // Keys are ordered numerically across small and big integers.
+        assert_eq!(
+            test_eval("let h = {}; h[-4294967296] = 1; h[5] = 2; h[-1] = 3; h[4294967296] = 4; h")
+                .to_string(),
+            "{-4294967296: 1, -1: 3, 5: 2, 4294967296: 4}"
+        );
+
+        eval_error_object(
+            test_eval("4294967296 + true"),
+            "type mismatch: INTEGER + BOOLEAN",
+        );
+    }
+
+    #[test]
+    fn floats() {
+        let input_expctdvalue = [
+            ("2.75", 2.75),
+            ("1e-9", 1e-9),
+            ("2.5E+3", 2500.0),
+            ("-1.5", -1.5),
+            ("0.1 + 0.2", 0.1 + 0.2),
+            ("1 + 0.5", 1.5),
+            ("0.5 * 4", 2.0),
+            ("7 / 2.0", 3.5),
+            ("4294967296 * 0.5", 2147483648.0),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i), Object::FLOAT(*v)));
+
+        let input_expctdvalue = [
+            ("1 == 1.0", true),
+            ("1.5 > 1", true),
+            ("2 < 1.5", false),
+            ("0.1 + 0.2 != 0.3", true),
+            ("!0.0", true),
+            ("!0.5", false),
+            ("if (0.0) { true } else { false }", false),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| eval_boolean_object(test_eval(i), *v));
+
+        eval_error_object(test_eval("1.5 + true"), "type mismatch: FLOAT + BOOLEAN");
+        eval_error_object(test_eval("{1.5: 1}"), "unusable as hash key: FLOAT");
+
+        // Results without a literal form are errors.
+        let input_expctderror = [
+            ("1.0 / 0", "division by zero: 1.0 / 0.0"),
+            ("0.0 / 0.0", "division by zero: 0.0 / 0.0"),
+            ("2.5 % 0", "division by zero: 2.5 % 0.0"),
+            ("1e300 * 1e10", "float too large: 1e300 * 10000000000.0"),
+            ("2.0 ** 1024", "float too large: 2.0 ** 1024.0"),
+            ("(1 << 2000) + 0.5", "float too large: inf + 0.5"),
+            ("(-8.0) ** 0.5", "not a number: -8.0 ** 0.5"),
+        ];
+
+        input_expctderror
+            .iter()
+            .for_each(|(i, e)| eval_error_object(test_eval(i), e));
+        assert_eq!(
+            match test_eval("1e308 * 10") {
+                Object::ERROR(e) => e.kind,
+                obj => panic!("Should be an error, got {obj}"),
+            },
+            ErrorKind::FloatOverflow
+        );
+    }
+
+    #[test]
+    fn float_display_round_trips() {
+        let input_expctdvalue = [
+            ("2.75", "2.75"),
+            ("2.0", "2.0"),
+            ("0.1 + 0.2", "0.30000000000000004"),
+            ("1e-9", "1e-9"),
+            ("1e300 * 10", "1e301"),
+            ("1 / 3.0", "0.3333333333333333"),
+        ];
+
+        input_expctdvalue.iter().for_each(|(i, v)| {
+            let displayed = test_eval(i).to_string();
+            assert_eq!(displayed, *v);
+            assert_eq!(test_eval(&displayed), test_eval(i));
+        });
+    }
+
+    #[test]
+    fn error_kinds() {
+        let input_expctdkind = [
+            ("5 + true;", ErrorKind::TypeMismatch),
+            ("-true", ErrorKind::UnknownOperator),
+            ("foobar", ErrorKind::UnknownIdentifier),
+            ("5(1)", ErrorKind::NotCallable),
+            ("len(1, 2)", ErrorKind::WrongArgumentCount),
+            ("len(1)", ErrorKind::InvalidArgument),
+            ("{}[[]]", ErrorKind::UnusableHashKey),
+            ("1[0]", ErrorKind::UnsupportedIndex),
+            ("[][0] = 1", ErrorKind::IndexOutOfBounds),
+            ("1 / 0", ErrorKind::DivisionByZero),
+            ("1 << -1", ErrorKind::InvalidOperand),
+            ("for (x in 1) {}", ErrorKind::NotIterable),
+            ("2 ** 10000000", ErrorKind::IntegerOverflow),
+        ];
+
+        input_expctdkind
+            .iter()
+            .for_each(|(i, kind)| match test_eval(i) {
+                Object::ERROR(e) => assert_eq!(e.kind, *kind),
+                obj => panic!("Should be an error, got {obj}"),
+            });
+    }
+
+    #[test]
+    fn error_span() {
+        let input_expctdposition = [
+            ("5 + true;", (1, 1)),
+            ("let a = 1;\nlet b = a + -true;", (2, 13)),
+            ("let f = fn(x) {\n  x + foo\n};\nf(1)", (2, 7)),
+            ("let a = [1, 2];\nlen(a, a)", (2, 1)),
+        ];
+
+        input_expctdposition
+            .iter()
+            .for_each(|(i, (line, column))| match test_eval(i) {
+                Object::ERROR(e) => {
+                    assert_eq!(e.span.unwrap().start, Position::new(*line, *column))
+                }
+                obj => panic!("Should be an error, got {obj}"),
+            });
+    }
+
+    #[test]
+    fn error_stack_trace() {
+        let input = "let inner = fn(x) { x + true };
+let outer = fn(x) { inner(x) + 1 };
+let result = outer(1);";
+
+        match test_eval(input) {
+            Object::ERROR(e) => {
+                let stack = e
+                    .stack
+                    .iter()
+                    .map(|frame| (frame.function.as_str(), frame.call_site.start))
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    stack,
+                    vec![
+                        ("outer", Position::new(3, 14)),
+                        ("inner", Position::new(2, 21))
+                    ]
+                );
+            }
+            obj => panic!("Should be an error, got {obj}"),
+        }
+    }
+
+    #[test]
+    fn tail_calls() {
+        let input_expctdvalue = [
+            (
+                "let count = fn(n, acc) { if (n == 0) { acc } else { count(n - 1, acc + 1) } };
+                 count(10000, 0)",
+                "10000",
+            ),
+            (
+                "let count = fn(n) { if (n == 0) { return \"done\"; } return count(n - 1); };
+                 count(10000)",
+                "done",
+            ),
+            (
+                "let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } };
+                 let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } };
+                 [even(10000), odd(7)]",
+                "[true, true]",
+            ),
+            (
+                "let map = fn(arr, f, acc) {
+                   if (len(arr) == 0) { return acc; }
+                   map(tail(arr), f, push(acc, f(first(arr))))
+                 };
+                 let range = fn(n, acc) { if (n == 0) { acc } else { range(n - 1, push(acc, n)) } };
+                 let doubled = map(range(5000, []), fn(x) { x * 2 }, []);
+                 [len(doubled), first(doubled), last(doubled)]",
+                "[5000, 10000, 2]",
+            ),
+            (
+                "let find = fn(n) { while (true) { if (n > 5) { return n; } return find(n + 1); } };
+                 find(0)",
+                "6",
+            ),
+            ("let f = fn(x) { len(x) }; f(\"abc\")", "3"),
+            ("let f = fn(x) { return x; }; f(1) + f(2)", "3"),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(test_eval(i).to_string(), *v, "{i}"));
+
+        // The frame of a function is replaced by the one of the function it tail calls.
+        let input = "let inner = fn(x) { x + true };
+let outer = fn(x) { inner(x) };
+let result = outer(1);";
+
+        match test_eval(input) {
+            Object::ERROR(e) => {
+                let stack = e
+                    .stack
+                    .iter()
+                    .map(|frame| (frame.function.as_str(), frame.call_site.start))
+                    .collect::<Vec<_>>();
+                assert_eq!(stack, vec![("inner", Position::new(2, 21))]);
+            }
+            obj => panic!("Should be an error, got {obj}"),
+        }
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let input = "let f = fn(x) { 1 + f(x) }; f(1)";
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        let executed = Vm::new().run(Compiler::new().compile(&program));
+        let mut evaluator = Evaluator::new();
+
+        // The evaluator runs out of native stack before reaching its maximum depth.
+        for (result, max_depth) in [
+            (evaluator.eval(program), None),
+            (executed, Some(vm::DEFAULT_MAX_DEPTH)),
+        ] {
+            match result {
+                Object::ERROR(e) => {
+                    let depth = max_depth.unwrap_or(e.stack.len());
+                    assert_eq!(e.kind, ErrorKind::StackOverflow);
+                    assert_eq!(
+                        e.message,
+                        format!("stack overflow: more than {depth} nested calls")
+                    );
+                    // The evaluator stops at whichever expression of the body finds the native
+                    // stack exhausted, the VM at the call.
+                    let start = e.span.unwrap().start;
+                    match max_depth {
+                        None => assert!((17..=23).contains(&start.column), "{start}"),
+                        Some(_) => assert_eq!(start, Position::new(1, 21)),
+                    }
+                    assert_eq!(e.stack.len(), depth);
+                    assert!(depth > 0 && depth <= vm::DEFAULT_MAX_DEPTH);
+                }
+                obj => panic!("Should be an error, got {obj}"),
+            }
+        }
+
+        // The evaluator is left usable, and tail calls do not count towards the limit.
+        let input =
+            "let count = fn(n) { if (n == 0) { \"done\" } else { count(n - 1) } }; count(1000)";
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        assert_eq!(evaluator.eval(program), Object::STRING("done".to_string()));
+    }
+
+    #[test]
+    fn default_max_depth() {
+        // Large enough for the default limit, unlike the stack of test threads.
+        let evaluated = std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(|| {
+                let input =
+                    "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; [f(900), f(1001)]";
+                let program = Parser::new(Lexer::new(input)).parse_program();
+                Evaluator::new()
+                    .with_stack_limit(63 << 20)
+                    .eval(program)
+                    .to_string()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(evaluated, "stack overflow: more than 1000 nested calls");
+    }
+
+    #[test]
+    fn default_stack_limit() {
+        // Threads spawned by `std` get 2MB of stack, which the default limit stays within.
+        let evaluated = std::thread::spawn(|| {
+            let input = "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(5000)";
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            match Evaluator::new().eval(program) {
+                Object::ERROR(e) => e.kind,
+                obj => panic!("Should be an error, got {obj}"),
+            }
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(evaluated, ErrorKind::StackOverflow);
+    }
+
+    #[test]
+    fn nested_blocks_stack_limit() {
+        // Blocks nested within each call use native stack too, between two calls.
+        let evaluated = std::thread::spawn(|| {
+            let nested = "if (true) { ".repeat(300) + "f(x)" + &" }".repeat(300);
+            let input = format!("let f = fn(x) {{ {nested} + 1 }}; f(1)");
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            match Evaluator::new().eval(program) {
+                Object::ERROR(e) => e.kind,
+                obj => panic!("Should be an error, got {obj}"),
+            }
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(evaluated, ErrorKind::StackOverflow);
+    }
+
+    #[test]
+    fn step_limit() {
+        let mut evaluator = Evaluator::new().with_step_limit(1000);
+        let eval = |evaluator: &mut Evaluator, input: &str| {
+            evaluator.eval(Parser::new(Lexer::new(input)).parse_program())
+        };
+
+        let inputs = [
+            "while (true) { }",
+            "for (i in 0..1000000) { }",
+            "let spin = fn(n) { spin(n + 1) }; spin(0)",
+            "let a = 1; let f = fn() { while (true) { a += 1; } }; f()",
+        ];
+
+        inputs.iter().for_each(|input| {
+            match eval(&mut evaluator, input) {
+                Object::ERROR(e) => {
+                    assert_eq!(e.kind, ErrorKind::BudgetExhausted, "{input}");
+                    assert_eq!(e.message, "budget exhausted: more than 1000 steps");
+                }
+                obj => panic!("Should be an error, got {obj}"),
+            }
+
+            // Every `eval` gets a new budget, and keeps the bindings of the previous ones.
+            assert_eq!(
+                eval(&mut evaluator, "let f = fn(x) { x * 2 }; f(21)"),
+                Object::INTEGER(42)
+            );
+        });
+        assert!(matches!(eval(&mut evaluator, "a"), Object::INTEGER(a) if a > 1));
+
+        // The `Vm` counts instructions instead.
+        let mut vm = Vm::new().with_step_limit(1000);
+        let mut run = |input: &str| {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            vm.run(Compiler::new().compile(&program))
+        };
+        inputs.iter().for_each(|input| {
+            match run(input) {
+                Object::ERROR(e) => {
+                    assert_eq!(e.kind, ErrorKind::BudgetExhausted, "{input}");
+                    assert_eq!(e.message, "budget exhausted: more than 1000 steps");
+                }
+                obj => panic!("Should be an error, got {obj}"),
+            }
+            assert_eq!(run("let f = fn(x) { x * 2 }; f(21)"), Object::INTEGER(42));
+        });
+    }
+
+    #[test]
+    fn time_limit() {
+        let limit = Duration::from_millis(50);
+        let mut evaluator = Evaluator::new().with_time_limit(limit);
+
+        let start = Instant::now();
+        match evaluator.eval(Parser::new(Lexer::new("while (true) { }")).parse_program()) {
+            Object::ERROR(e) => {
+                assert_eq!(e.kind, ErrorKind::BudgetExhausted);
+                assert_eq!(e.message, "budget exhausted: time limit of 50ms reached");
+            }
+            obj => panic!("Should be an error, got {obj}"),
+        }
+        assert!(start.elapsed() >= limit);
+
+        let program = Parser::new(Lexer::new("1 + 1")).parse_program();
+        assert_eq!(evaluator.eval(program), Object::INTEGER(2));
+
+        let mut vm = Vm::new().with_time_limit(limit);
+        let program = Parser::new(Lexer::new("while (true) { }")).parse_program();
+        let start = Instant::now();
+        match vm.run(Compiler::new().compile(&program)) {
+            Object::ERROR(e) => {
+                assert_eq!(e.kind, ErrorKind::BudgetExhausted);
+                assert_eq!(e.message, "budget exhausted: time limit of 50ms reached");
+            }
+            obj => panic!("Should be an error, got {obj}"),
+        }
+        assert!(start.elapsed() >= limit);
+    }
+
+    #[test]
+    fn memory_limit() {
+        let mut evaluator = Evaluator::new().with_memory_limit(1 << 20);
+        let eval = |evaluator: &mut Evaluator, input: &str| {
+            evaluator.eval(Parser::new(Lexer::new(input)).parse_program())
+        };
+
+        let inputs = [
+            "let s = \"ab\"; while (true) { s = s + s; }",
+            "let s = \"\"; while (true) { s += \"abc\"; }",
+            "let a = [1]; while (true) { for (x in a) { a = push(a, x); } }",
+            "let f = fn(n) { [n, n, n, n, n, n, n, n] }; let i = 0; while (true) { f(i); i += 1; }",
+            "let h = {}; let i = 0; while (true) { h[i] = i; i += 1; }",
+            "let i = 0; while (true) { {i: i, -i: i}; i += 1; }",
+            "let x = 1; while (true) { x = x * 3; }",
+        ];
+
+        inputs
+            .iter()
+            .for_each(|input| match eval(&mut evaluator, input) {
+                Object::ERROR(e) => {
+                    assert_eq!(e.kind, ErrorKind::OutOfMemory, "{input}");
+                    assert_eq!(
+                        e.message,
+                        "out of memory: more than 1048576 bytes allocated"
+                    );
+                }
+                obj => panic!("Should be an error, got {obj}"),
+            });
+
+        // The `Vm` counts the same allocations.
+        let mut vm = Vm::new().with_memory_limit(1 << 20);
+        inputs.iter().for_each(|input| {
+            let program = Parser::new(Lexer::new(*input)).parse_program();
+            match vm.run(Compiler::new().compile(&program)) {
+                Object::ERROR(e) => assert_eq!(e.kind, ErrorKind::OutOfMemory, "{input}"),
+                obj => panic!("Should be an error, got {obj}"),
+            }
+        });
+
+        // Replacing the value of an existing key does not grow a hash.
+        let input = "let h = {1: 0}; for (i in 0..100000) { h[1] = i; } h[1]";
+        assert_eq!(eval(&mut evaluator, input), Object::INTEGER(99999));
+
+        // Every `eval` can allocate up to the limit again.
+        let input = "let a = []; for (i in 0..1000) { a = push(a, \"item\"); } len(a)";
+        assert_eq!(eval(&mut evaluator, input), Object::INTEGER(1000));
+        assert_eq!(eval(&mut evaluator, input), Object::INTEGER(1000));
+    }
+
+    #[test]
+    fn interrupt() {
+        let mut evaluator = Evaluator::new();
+        let eval = |evaluator: &mut Evaluator, input: &str| {
+            evaluator.eval(Parser::new(Lexer::new(input)).parse_program())
+        };
+
+        // An interrupt received before an evaluation aborts it, and is consumed.
+        evaluator.interrupt_handle().interrupt();
+        match eval(&mut evaluator, "let n = 0; n") {
+            Object::ERROR(e) => assert_eq!(e.kind, ErrorKind::Interrupted),
+            obj => panic!("Should be an error, got {obj}"),
+        }
+        assert_eq!(eval(&mut evaluator, "let n = 0; n"), Object::INTEGER(0));
+
+        // Unless it is cleared first.
+        evaluator.interrupt_handle().interrupt();
+        evaluator.interrupt_handle().clear();
+        assert_eq!(eval(&mut evaluator, "n"), Object::INTEGER(0));
+
+        let handle = evaluator.interrupt_handle();
+        let interrupter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            handle.interrupt();
+        });
+        match eval(
+            &mut evaluator,
+            "let f = fn() { while (true) { n += 1; } }; f()",
+        ) {
+            Object::ERROR(e) => {
+                assert_eq!(e.kind, ErrorKind::Interrupted);
+                assert_eq!(e.message, "interrupted");
+                assert_eq!(e.stack.len(), 1);
+            }
+            obj => panic!("Should be an error, got {obj}"),
+        }
+        interrupter.join().unwrap();
+
+        assert_eq!(eval(&mut evaluator, "n > 0"), Object::BOOLEAN(true));
+
+        let mut vm = Vm::new();
+        let handle = vm.interrupt_handle();
+        let mut run = |input: &str| {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            vm.run(Compiler::new().compile(&program))
+        };
+        let interrupter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            handle.interrupt();
+        });
+        match run("let n = 0; let f = fn() { while (true) { n += 1; } }; f()") {
+            Object::ERROR(e) => {
+                assert_eq!(e.kind, ErrorKind::Interrupted);
+                assert_eq!(e.stack.len(), 1);
+            }
+            obj => panic!("Should be an error, got {obj}"),
+        }
+        interrupter.join().unwrap();
+        assert_eq!(run("n > 0"), Object::BOOLEAN(true));
+    }
+
+    #[test]
+    fn error_stack_is_unwound() {
+        let input = "let f = fn(x) { x }; f(1); f(2) + true";
+
+        match test_eval(input) {
+            Object::ERROR(e) => assert!(e.stack.is_empty()),
+            obj => panic!("Should be an error, got {obj}"),
+        }
+    }
+
+    #[test]
+    fn cycle_collection() {
+        let input = "let make = fn() { let g = fn() { g }; g }; make(); make()";
+        let program = || Parser::new(Lexer::new(input)).parse_program();
+
+        let mut evaluator = Evaluator::new();
+        let kept = match evaluator.eval(program()) {
+            Object::FUNCTION(f) => f.env,
+            obj => panic!("Should be a function, got {obj}"),
+        };
+        assert_eq!(evaluator.collect_cycles(), 1);
+        let released = Rc::downgrade(&kept);
+        drop(kept);
+        // Values still held by the host outlive the evaluator.
+        let kept = match evaluator.eval(program()) {
+            Object::FUNCTION(f) => f.env,
+            obj => panic!("Should be a function, got {obj}"),
+        };
+        drop(evaluator);
+        assert!(released.upgrade().is_none());
+        assert!(kept.borrow().get("g").is_some());
+
+        let mut vm = Vm::new();
+        let kept = match vm.run(Compiler::new().compile(&program())) {
+            Object::CLOSURE(c) => c.env,
+            obj => panic!("Should be a closure, got {obj}"),
+        };
+        assert_eq!(vm.collect_cycles(), 1);
+        let released = Rc::downgrade(&kept);
+        drop(kept);
+        // Values still held by the host outlive the vm.
+        let kept = match vm.run(Compiler::new().compile(&program())) {
+            Object::CLOSURE(c) => c.env,
+            obj => panic!("Should be a closure, got {obj}"),
+        };
+        drop(vm);
+        assert!(released.upgrade().is_none());
+        assert!(kept.borrow().get("g").is_some());
+
+        // Collections run during evaluations, while the second function is only held by the
+        // elements the loop goes over.
+        let input = "
+            let make = fn() { let a = [0, fn() { a }]; a };
+            let garbage = fn() { let g = fn() { g }; 0 };
+            let n = 0;
+            let first = true;
+            for (f in make()) {
+                if (first) {
+                    for (i in 0..3000) { garbage(); }
+                    first = false;
+                } else {
+                    n = len(f());
+                }
+            }
+            n";
+        assert_eq!(test_eval(input), Object::INTEGER(2));
     }
 }
@@ -1,12 +1,17 @@
-use crate::token::token::Token;
+use crate::token::token::{Position, Span, SpannedToken, Token};
 use lazy_static::lazy_static;
-use std::{char, collections::HashMap, usize};
+use std::{char, collections::HashMap};
 
+/// Scans the source in a single pass, `index` and `read_index` are byte offsets
+/// of the current and next character.
 pub struct Lexer {
     input: String,
     index: usize,
     read_index: usize,
     ch: char,
+    line: usize,
+    column: usize,
+    emit_comments: bool,
 }
 
 lazy_static! {
@@ -19,6 +24,11 @@ lazy_static! {
         keywords.insert(String::from("true"), Token::TRUE);
         keywords.insert(String::from("false"), Token::FALSE);
         keywords.insert(String::from("return"), Token::RETURN);
+        keywords.insert(String::from("while"), Token::WHILE);
+        keywords.insert(String::from("break"), Token::BREAK);
+        keywords.insert(String::from("continue"), Token::CONTINUE);
+        keywords.insert(String::from("for"), Token::FOR);
+        keywords.insert(String::from("in"), Token::IN);
         keywords
     };
 }
@@ -30,61 +40,225 @@ impl Lexer {
             index: 0,
             read_index: 0,
             ch: '\0',
+            line: 1,
+            column: 0,
+            emit_comments: false,
         };
         lexer.next_token();
         lexer
     }
 
-    // TODO!: Support UNICODE
+    /// Lexer that returns comments as `Token::COMMENT` instead of skipping them.
+    pub fn with_comments<S: Into<String>>(input: S) -> Self {
+        let mut lexer = Self::new(input);
+        lexer.emit_comments = true;
+        lexer
+    }
+
     pub fn read_char(&mut self) {
-        if self.read_index >= self.input.len() {
-            self.ch = '\0';
-        } else {
-            self.ch = self.input.chars().nth(self.read_index).unwrap();
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if self.read_index <= self.input.len() {
+            self.column += 1;
+        }
+        self.index = self.read_index.min(self.input.len());
+        match self.input[self.index..].chars().next() {
+            Some(c) => {
+                self.ch = c;
+                self.read_index = self.index + c.len_utf8();
+            }
+            None => {
+                self.ch = '\0';
+                self.read_index = self.input.len() + 1;
+            }
         }
-        self.index = self.read_index;
-        self.read_index += 1;
     }
 
     pub fn peek_char(&mut self) -> char {
-        if self.read_index >= self.input.len() {
-            '\0'
-        } else {
-            self.input.chars().nth(self.read_index).unwrap()
-        }
+        self.peek_nth_char(0)
+    }
+
+    fn peek_nth_char(&self, n: usize) -> char {
+        self.input
+            .get(self.read_index..)
+            .and_then(|rest| rest.chars().nth(n))
+            .unwrap_or('\0')
     }
 
     pub fn read_identifier(&mut self) -> String {
         let start_index = self.index;
-        while Self::is_ident_letter(self.ch) {
+        while Self::is_ident_letter(self.ch) || self.ch.is_alphanumeric() {
             self.read_char();
         }
         self.input[start_index..self.index].to_string()
     }
 
-    pub fn read_number(&mut self) -> String {
+    /// Reads an integer or a float with an optional fraction and exponent, like `1.5e-3`.
+    pub fn read_number(&mut self) -> Token {
         let start_index = self.index;
-        while self.ch.is_digit(10) {
+        let mut is_float = false;
+        self.read_digits();
+
+        if self.ch == '.' && self.peek_char().is_ascii_digit() {
+            is_float = true;
+            self.read_char();
+            self.read_digits();
+        }
+
+        let exponent = match (self.peek_char(), self.peek_nth_char(1)) {
+            ('+' | '-', digit) | (digit, _) => digit.is_ascii_digit(),
+        };
+        if matches!(self.ch, 'e' | 'E') && exponent {
+            is_float = true;
+            self.read_char();
+            if matches!(self.ch, '+' | '-') {
+                self.read_char();
+            }
+            self.read_digits();
+        }
+
+        let literal = self.input[start_index..self.index].to_string();
+        if is_float {
+            Token::FLOAT(literal)
+        } else {
+            Token::INT(literal)
+        }
+    }
+
+    fn read_digits(&mut self) {
+        while self.ch.is_ascii_digit() {
             self.read_char();
         }
-        self.input[start_index..self.index].to_string()
     }
 
-    pub fn read_string(&mut self) -> String {
-        let start_index = self.index + 1;
+    /// Reads a string literal, decoding `\" \\ \n \t \r \u{...}` escapes.
+    ///
+    /// The whole literal is consumed even when an escape is invalid, so lexing
+    /// resumes after the closing quote.
+    pub fn read_string(&mut self) -> Token {
+        let mut value = String::new();
+        let mut invalid_escape = None;
         loop {
             self.read_char();
             match self.ch {
-                '\"' | '\0' => break,
-                _ => continue,
+                '"' => break,
+                '\0' => return Token::UNTERMINATED,
+                '\\' => {
+                    self.read_char();
+                    match self.ch {
+                        '"' => value.push('"'),
+                        '\\' => value.push('\\'),
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        'r' => value.push('\r'),
+                        'u' => match self.read_unicode_escape() {
+                            Ok(c) => value.push(c),
+                            Err(sequence) => {
+                                invalid_escape.get_or_insert(sequence);
+                            }
+                        },
+                        '\0' => return Token::UNTERMINATED,
+                        c => {
+                            invalid_escape.get_or_insert(format!("\\{c}"));
+                        }
+                    }
+                }
+                c => value.push(c),
             }
         }
-        self.input[start_index..self.index].to_string()
+
+        match invalid_escape {
+            Some(sequence) => Token::BADESCAPE(sequence),
+            None => Token::STRING(value),
+        }
+    }
+
+    /// Reads the `{...}` part of a `\u{...}` escape, returning the sequence read so far on error.
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        let mut sequence = String::from("\\u");
+        if self.peek_char() != '{' {
+            return Err(sequence);
+        }
+        self.read_char();
+        sequence.push('{');
+
+        while self.peek_char().is_ascii_hexdigit() && sequence.len() < 9 {
+            self.read_char();
+            sequence.push(self.ch);
+        }
+        if self.peek_char() != '}' {
+            return Err(sequence);
+        }
+        self.read_char();
+        sequence.push('}');
+
+        u32::from_str_radix(&sequence[3..sequence.len() - 1], 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(sequence)
+    }
+
+    fn read_line_comment(&mut self) -> Token {
+        let start_index = self.index;
+        while self.ch != '\n' && self.ch != '\0' {
+            self.read_char();
+        }
+        Token::COMMENT(self.input[start_index..self.index].to_string())
+    }
+
+    /// Reads a `/* */` comment, which can contain nested block comments.
+    fn read_block_comment(&mut self) -> Token {
+        let start_index = self.index;
+        self.read_char();
+        self.read_char();
+
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.ch, self.peek_char()) {
+                ('\0', _) => return Token::OPENCOMMENT,
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                }
+                _ => (),
+            }
+            self.read_char();
+        }
+        Token::COMMENT(self.input[start_index..self.index].to_string())
+    }
+
+    pub fn position(&self) -> Position {
+        Position::new(self.line, self.column)
     }
 
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        self.next_spanned_token().token
+    }
+
+    pub fn next_spanned_token(&mut self) -> SpannedToken {
+        loop {
+            self.skip_whitespace();
+
+            let start = self.position();
+            let token = self.read_token();
+            if let Token::COMMENT(_) = token {
+                if !self.emit_comments {
+                    continue;
+                }
+            }
+            return SpannedToken {
+                token,
+                span: Span::new(start, self.position()),
+            };
+        }
+    }
 
+    fn read_token(&mut self) -> Token {
         let tok = match self.ch {
             '=' => {
                 if self.peek_char() == '=' {
@@ -94,12 +268,91 @@ impl Lexer {
                     Token::ASSIGN
                 }
             }
-            '+' => Token::PLUS,
-            '-' => Token::SUB,
-            '/' => Token::DIV,
-            '*' => Token::MUL,
-            '>' => Token::GT,
-            '<' => Token::LT,
+            '+' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Token::PLUSASSIGN
+                } else {
+                    Token::PLUS
+                }
+            }
+            '-' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    Token::SUBASSIGN
+                } else {
+                    Token::SUB
+                }
+            }
+            '/' => match self.peek_char() {
+                '/' => return self.read_line_comment(),
+                '*' => return self.read_block_comment(),
+                '=' => {
+                    self.read_char();
+                    Token::DIVASSIGN
+                }
+                _ => Token::DIV,
+            },
+            '*' => match self.peek_char() {
+                '*' => {
+                    self.read_char();
+                    Token::POW
+                }
+                '=' => {
+                    self.read_char();
+                    Token::MULASSIGN
+                }
+                _ => Token::MUL,
+            },
+            '%' => Token::MOD,
+            '>' => match self.peek_char() {
+                '=' => {
+                    self.read_char();
+                    Token::GE
+                }
+                '>' => {
+                    self.read_char();
+                    Token::SHR
+                }
+                _ => Token::GT,
+            },
+            '<' => match self.peek_char() {
+                '=' => {
+                    self.read_char();
+                    Token::LE
+                }
+                '<' => {
+                    self.read_char();
+                    Token::SHL
+                }
+                _ => Token::LT,
+            },
+            '&' => {
+                if self.peek_char() == '&' {
+                    self.read_char();
+                    Token::AND
+                } else {
+                    Token::BITAND
+                }
+            }
+            '|' => {
+                if self.peek_char() == '|' {
+                    self.read_char();
+                    Token::OR
+                } else {
+                    Token::BITOR
+                }
+            }
+            '^' => Token::BITXOR,
+            '~' => Token::TILDE,
+            '.' => {
+                if self.peek_char() == '.' {
+                    self.read_char();
+                    Token::DOTDOT
+                } else {
+                    Token::ILLEGAL(String::from("."))
+                }
+            }
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
@@ -112,7 +365,7 @@ impl Lexer {
             ',' => Token::COMMA,
             ';' => Token::SEMICOLON,
             ':' => Token::COLON,
-            '"' => Token::STRING(self.read_string()),
+            '"' => self.read_string(),
 
             '(' => Token::LPAREN,
             ')' => Token::RPAREN,
@@ -125,8 +378,8 @@ impl Lexer {
             c => {
                 if Self::is_ident_letter(c) {
                     return Self::is_keyword(self.read_identifier());
-                } else if c.is_digit(10) {
-                    return Token::INT(self.read_number());
+                } else if c.is_ascii_digit() {
+                    return self.read_number();
                 } else {
                     Token::ILLEGAL(c.to_string())
                 }
@@ -141,14 +394,11 @@ impl Lexer {
     }
 
     fn is_keyword(ident: String) -> Token {
-        KEYWORDS
-            .get(&ident)
-            .unwrap_or(&Token::IDENT(String::from(ident)))
-            .clone()
+        KEYWORDS.get(&ident).unwrap_or(&Token::IDENT(ident)).clone()
     }
 
     fn skip_whitespace(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
+        while self.ch.is_whitespace() {
             self.read_char();
         }
     }
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod token_test {
     use crate::lexer::lexer::Lexer;
-    use crate::token::token::Token;
+    use crate::token::token::{Position, Token};
 
     #[test]
     fn basics_tokens() {
@@ -94,7 +94,7 @@ let add = fn(x, y) {
 };
 
 let result = add(five, ten);
-!-/*5;
+!-/ *5;
 5 < 10 > 5;
 
 if (5 < 10) {
@@ -201,7 +201,7 @@ let add = fn(x, y) {
 };
 
 let result = add(five, ten);
-!-/*5;
+!-/ *5;
 5 < 10 > 5;
 
 if (5 < 10) {
@@ -314,4 +314,326 @@ if (5 < 10) {
             .iter()
             .for_each(|token| assert_eq!(lexer.next_token(), *token));
     }
+
+    #[test]
+    fn token_spans() {
+        let input = "let five = 5;
+  five == \"ab\"";
+
+        let expected = vec![
+            (Token::LET, (1, 1), (1, 4)),
+            (Token::IDENT(String::from("five")), (1, 5), (1, 9)),
+            (Token::ASSIGN, (1, 10), (1, 11)),
+            (Token::INT(String::from("5")), (1, 12), (1, 13)),
+            (Token::SEMICOLON, (1, 13), (1, 14)),
+            (Token::IDENT(String::from("five")), (2, 3), (2, 7)),
+            (Token::EQ, (2, 8), (2, 10)),
+            (Token::STRING(String::from("ab")), (2, 11), (2, 15)),
+            (Token::EOF, (2, 15), (2, 15)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected.iter().for_each(|(token, start, end)| {
+            let spanned = lexer.next_spanned_token();
+            assert_eq!(spanned.token, *token);
+            assert_eq!(spanned.span.start, Position::new(start.0, start.1));
+            assert_eq!(spanned.span.end, Position::new(end.0, end.1));
+        });
+    }
+
+    #[test]
+    fn number_tokens() {
+        let input = "3.14 1e-9 2.5E+3 7e2 10 1.x 4e 5..";
+
+        let expected_tokens = vec![
+            Token::FLOAT(String::from("3.14")),
+            Token::FLOAT(String::from("1e-9")),
+            Token::FLOAT(String::from("2.5E+3")),
+            Token::FLOAT(String::from("7e2")),
+            Token::INT(String::from("10")),
+            Token::INT(String::from("1")),
+            Token::ILLEGAL(String::from(".")),
+            Token::IDENT(String::from("x")),
+            Token::INT(String::from("4")),
+            Token::IDENT(String::from("e")),
+            Token::INT(String::from("5")),
+            Token::DOTDOT,
+            Token::EOF,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected_tokens
+            .iter()
+            .for_each(|token| assert_eq!(lexer.next_token(), *token));
+    }
+
+    #[test]
+    fn logical_operators() {
+        let input = "a && b || !c & d | e";
+
+        let expected_tokens = vec![
+            Token::IDENT(String::from("a")),
+            Token::AND,
+            Token::IDENT(String::from("b")),
+            Token::OR,
+            Token::BANG,
+            Token::IDENT(String::from("c")),
+            Token::BITAND,
+            Token::IDENT(String::from("d")),
+            Token::BITOR,
+            Token::IDENT(String::from("e")),
+            Token::EOF,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected_tokens
+            .iter()
+            .for_each(|token| assert_eq!(lexer.next_token(), *token));
+    }
+
+    #[test]
+    fn arithmetic_and_bitwise_operators() {
+        let input = "a <= b >= c % d ** e & f | g ^ h << i >> j ~k < > * ***";
+
+        let expected_tokens = vec![
+            Token::IDENT(String::from("a")),
+            Token::LE,
+            Token::IDENT(String::from("b")),
+            Token::GE,
+            Token::IDENT(String::from("c")),
+            Token::MOD,
+            Token::IDENT(String::from("d")),
+            Token::POW,
+            Token::IDENT(String::from("e")),
+            Token::BITAND,
+            Token::IDENT(String::from("f")),
+            Token::BITOR,
+            Token::IDENT(String::from("g")),
+            Token::BITXOR,
+            Token::IDENT(String::from("h")),
+            Token::SHL,
+            Token::IDENT(String::from("i")),
+            Token::SHR,
+            Token::IDENT(String::from("j")),
+            Token::TILDE,
+            Token::IDENT(String::from("k")),
+            Token::LT,
+            Token::GT,
+            Token::MUL,
+            Token::POW,
+            Token::MUL,
+            Token::EOF,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected_tokens
+            .iter()
+            .for_each(|token| assert_eq!(lexer.next_token(), *token));
+    }
+
+    #[test]
+    fn loop_tokens() {
+        let input = "while (x) { break; continue } for (i in 0..n) {} . .. ...";
+
+        let expected_tokens = vec![
+            Token::WHILE,
+            Token::LPAREN,
+            Token::IDENT(String::from("x")),
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::BREAK,
+            Token::SEMICOLON,
+            Token::CONTINUE,
+            Token::RBRACE,
+            Token::FOR,
+            Token::LPAREN,
+            Token::IDENT(String::from("i")),
+            Token::IN,
+            Token::INT(String::from("0")),
+            Token::DOTDOT,
+            Token::IDENT(String::from("n")),
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::RBRACE,
+            Token::ILLEGAL(String::from(".")),
+            Token::DOTDOT,
+            Token::DOTDOT,
+            Token::ILLEGAL(String::from(".")),
+            Token::EOF,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected_tokens
+            .iter()
+            .for_each(|token| assert_eq!(lexer.next_token(), *token));
+    }
+
+    #[test]
+    fn assignment_tokens() {
+        let input = "x = 1; x += 2; x -= 3; x *= 4; x /= 5; x == 6; x ** 7; // 8";
+
+        let expected_tokens = vec![
+            Token::IDENT(String::from("x")),
+            Token::ASSIGN,
+            Token::INT(String::from("1")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("x")),
+            Token::PLUSASSIGN,
+            Token::INT(String::from("2")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("x")),
+            Token::SUBASSIGN,
+            Token::INT(String::from("3")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("x")),
+            Token::MULASSIGN,
+            Token::INT(String::from("4")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("x")),
+            Token::DIVASSIGN,
+            Token::INT(String::from("5")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("x")),
+            Token::EQ,
+            Token::INT(String::from("6")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("x")),
+            Token::POW,
+            Token::INT(String::from("7")),
+            Token::SEMICOLON,
+            Token::EOF,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected_tokens
+            .iter()
+            .for_each(|token| assert_eq!(lexer.next_token(), *token));
+    }
+
+    #[test]
+    fn string_escapes() {
+        let input = r#""a\"b" "\\ \n\t\r" "\u{48}\u{1F600}" "\q" "\u{110000}" "\u41" "ok" "open"#;
+
+        let expected = vec![
+            (Token::STRING(String::from("a\"b")), (1, 1)),
+            (Token::STRING(String::from("\\ \n\t\r")), (1, 8)),
+            (Token::STRING(String::from("H\u{1F600}")), (1, 20)),
+            (Token::BADESCAPE(String::from("\\q")), (1, 38)),
+            (Token::BADESCAPE(String::from("\\u{110000}")), (1, 43)),
+            (Token::BADESCAPE(String::from("\\u")), (1, 56)),
+            (Token::STRING(String::from("ok")), (1, 63)),
+            (Token::UNTERMINATED, (1, 68)),
+            (Token::EOF, (1, 73)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected.iter().for_each(|(token, start)| {
+            let spanned = lexer.next_spanned_token();
+            assert_eq!(spanned.token, *token);
+            assert_eq!(spanned.span.start, Position::new(start.0, start.1));
+        });
+    }
+
+    #[test]
+    fn unicode_tokens() {
+        let input = "let café = \"日本語\";\nλx2 + café_ü";
+
+        let expected = vec![
+            (Token::LET, (1, 1), (1, 4)),
+            (Token::IDENT(String::from("café")), (1, 5), (1, 9)),
+            (Token::ASSIGN, (1, 10), (1, 11)),
+            (Token::STRING(String::from("日本語")), (1, 12), (1, 17)),
+            (Token::SEMICOLON, (1, 17), (1, 18)),
+            (Token::IDENT(String::from("λx2")), (2, 1), (2, 4)),
+            (Token::PLUS, (2, 5), (2, 6)),
+            (Token::IDENT(String::from("café_ü")), (2, 7), (2, 13)),
+            (Token::EOF, (2, 13), (2, 13)),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected.iter().for_each(|(token, start, end)| {
+            let spanned = lexer.next_spanned_token();
+            assert_eq!(spanned.token, *token);
+            assert_eq!(spanned.span.start, Position::new(start.0, start.1));
+            assert_eq!(spanned.span.end, Position::new(end.0, end.1));
+        });
+    }
+
+    #[test]
+    fn large_input() {
+        let line = "let ñandú = \"ü→€\" + 12;\n";
+        let lines = 100_000;
+        let mut lexer = Lexer::new(line.repeat(lines));
+
+        let mut count = 0;
+        let last = loop {
+            let spanned = lexer.next_spanned_token();
+            if spanned.token == Token::EOF {
+                break spanned;
+            }
+            count += 1;
+        };
+
+        assert_eq!(count, 7 * lines);
+        assert_eq!(last.span.start, Position::new(lines + 1, 1));
+    }
+
+    #[test]
+    fn comments() {
+        let input = "let a = 1; // one
+/* block /* nested */ still comment */ a / 2 /**/
+// trailing";
+
+        let expected_tokens = vec![
+            Token::LET,
+            Token::IDENT(String::from("a")),
+            Token::ASSIGN,
+            Token::INT(String::from("1")),
+            Token::SEMICOLON,
+            Token::IDENT(String::from("a")),
+            Token::DIV,
+            Token::INT(String::from("2")),
+            Token::EOF,
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        expected_tokens
+            .iter()
+            .for_each(|token| assert_eq!(lexer.next_token(), *token));
+
+        let mut lexer = Lexer::new("1 /* open /* nested */ 2");
+        assert_eq!(lexer.next_token(), Token::INT(String::from("1")));
+        assert_eq!(lexer.next_token(), Token::OPENCOMMENT);
+    }
+
+    #[test]
+    fn comment_trivia() {
+        let input = "// one\nx /* two\n */ y";
+
+        let expected = [
+            (Token::COMMENT(String::from("// one")), (1, 1), (1, 7)),
+            (Token::IDENT(String::from("x")), (2, 1), (2, 2)),
+            (Token::COMMENT(String::from("/* two\n */")), (2, 3), (3, 4)),
+            (Token::IDENT(String::from("y")), (3, 5), (3, 6)),
+            (Token::EOF, (3, 6), (3, 6)),
+        ];
+
+        let mut lexer = Lexer::with_comments(input);
+
+        expected.iter().for_each(|(token, start, end)| {
+            let spanned = lexer.next_spanned_token();
+            assert_eq!(spanned.token, *token);
+            assert_eq!(spanned.span.start, Position::new(start.0, start.1));
+            assert_eq!(spanned.span.end, Position::new(end.0, end.1));
+        });
+    }
 }
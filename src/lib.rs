@@ -1,6 +1,12 @@
+#![allow(clippy::module_inception)]
+#![cfg_attr(test, allow(clippy::useless_vec, clippy::len_zero))]
+
 pub mod ast;
+pub mod cache;
+pub mod compiler;
 pub mod evaluator;
 pub mod lexer;
 pub mod object;
 pub mod parser;
 pub mod token;
+pub mod vm;
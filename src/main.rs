@@ -1,39 +1,230 @@
-use std::io::{self, Write};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::Path,
+    process, thread,
+};
 
 use monkey::{
-    evaluator::evaluator::Evaluator, lexer::lexer::Lexer, object::object::Object,
+    ast::ast::Program,
+    cache::cache,
+    compiler::compiler::Compiler,
+    evaluator::evaluator::Evaluator,
+    lexer::lexer::Lexer,
+    object::{
+        error::{InterruptHandle, RuntimeError},
+        object::Object,
+    },
     parser::parser::Parser,
+    vm::vm::Vm,
 };
 
 const PROMPT: &str = "@ ";
+const SOURCE_NAME: &str = "<stdin>";
+const CACHE_EXTENSION: &str = "mkc";
+/// Native stack of the interpreter thread, enough for `DEFAULT_MAX_DEPTH` nested calls of the
+/// evaluator even in debug builds.
+const STACK_SIZE: usize = 64 << 20;
+/// Part of `STACK_SIZE` the evaluator may use, the rest is left to the REPL itself.
+const STACK_LIMIT: usize = STACK_SIZE - (1 << 20);
+
+enum Backend {
+    Evaluator(Evaluator),
+    Vm(Vm),
+}
 
+impl Backend {
+    fn run(&mut self, program: Program) -> Object {
+        match self {
+            Backend::Evaluator(evaluator) => evaluator.eval(program),
+            Backend::Vm(vm) => vm.run(Compiler::new().compile(&program)),
+        }
+    }
+
+    fn interrupt_handle(&self) -> InterruptHandle {
+        match self {
+            Backend::Evaluator(evaluator) => evaluator.interrupt_handle(),
+            Backend::Vm(vm) => vm.interrupt_handle(),
+        }
+    }
+}
+
+/// Usage: `monkey [--vm] [script]`, starts the REPL when no script is given.
+///
+/// `--vm` runs programs on the bytecode virtual machine instead of the evaluator.
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let backend = if args.iter().any(|arg| arg == "--vm") {
+                Backend::Vm(Vm::new())
+            } else {
+                Backend::Evaluator(Evaluator::new().with_stack_limit(STACK_LIMIT))
+            };
+
+            match args.iter().find(|arg| !arg.starts_with("--")) {
+                Some(script) => run_script(Path::new(script), backend),
+                None => repl(backend),
+            }
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("cannot start the interpreter: {err}");
+            process::exit(1);
+        });
+
+    if interpreter.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn repl(mut backend: Backend) {
     println!("Monkey Programming Language !");
+    let interrupt = backend.interrupt_handle();
+    sigint::install(interrupt.clone());
 
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let input = &mut String::new();
-    let mut evaluator = Evaluator::new();
 
     loop {
         input.clear();
         print!("{PROMPT}");
         let _ = stdout.flush();
-        let _ = stdin.read_line(input);
+        if let Ok(0) | Err(_) = stdin.read_line(input) {
+            println!();
+            return;
+        }
 
-        let lexer = Lexer::new(input.clone());
-        let mut parser = Parser::new(lexer);
+        let Some(program) = parse(input, SOURCE_NAME) else {
+            continue;
+        };
 
-        let program = parser.parse_program();
+        // Ctrl-C pressed after the previous evaluation returned must not abort this one.
+        interrupt.clear();
+        sigint::set_evaluating(true);
+        let result = backend.run(program);
+        sigint::set_evaluating(false);
 
-        if parser.errors().len() != 0 {
-            parser.errors().iter().for_each(|err| println!("{err}"));
-            continue;
+        match result {
+            Object::FUNCTION(_) | Object::CLOSURE(_) => continue,
+            Object::ERROR(e) => report_error(&e, SOURCE_NAME),
+            e => println!("{}", e),
         }
+    }
+}
 
-        match evaluator.eval(program) {
-            Object::FUNCTION(_) => continue,
-            e => println!("{}", e),
+/// Runs a script, reusing the program cached next to it when the source did not change.
+fn run_script(path: &Path, mut backend: Backend) {
+    let source_name = path.display().to_string();
+    let source = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("{source_name}: {err}");
+        process::exit(1);
+    });
+
+    let cache_path = path.with_extension(CACHE_EXTENSION);
+    let program = match cache::load(&cache_path, &source) {
+        Ok(program) => program,
+        Err(_) => {
+            let Some(program) = parse(&source, &source_name) else {
+                process::exit(1);
+            };
+            if let Err(err) = cache::store(&cache_path, &program, &source) {
+                eprintln!("{}: {err}", cache_path.display());
+            }
+            program
+        }
+    };
+
+    match backend.run(program) {
+        Object::ERROR(e) => {
+            report_error(&e, &source_name);
+            process::exit(1);
         }
+        Object::NULL | Object::FUNCTION(_) | Object::CLOSURE(_) => (),
+        result => println!("{result}"),
     }
 }
+
+fn parse(source: &str, source_name: &str) -> Option<Program> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+
+    if parser.errors().is_empty() {
+        return Some(program);
+    }
+
+    parser
+        .errors()
+        .iter()
+        .for_each(|err| println!("{source_name}:{}: {err}", err.span()));
+    None
+}
+
+fn report_error(e: &RuntimeError, source_name: &str) {
+    match e.span {
+        Some(span) => println!("{source_name}:{span}: {e}"),
+        None => println!("{e}"),
+    }
+
+    // Runaway recursion produces long runs of the same frame.
+    let mut frames = e.stack.iter().rev().peekable();
+    while let Some(frame) = frames.next() {
+        println!("    {frame}");
+        let mut repeated = 0;
+        while frames
+            .next_if(|next| {
+                next.function == frame.function && next.call_site.start == frame.call_site.start
+            })
+            .is_some()
+        {
+            repeated += 1;
+        }
+        if repeated > 0 {
+            println!("    ... repeated {repeated} more times");
+        }
+    }
+}
+
+/// Ctrl-C in the REPL interrupts the evaluation in progress, keeping the session and its
+/// bindings. At the prompt it still exits.
+#[cfg(unix)]
+mod sigint {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use monkey::object::error::InterruptHandle;
+    use signal_hook::{consts::SIGINT, low_level};
+
+    static EVALUATING: AtomicBool = AtomicBool::new(false);
+
+    /// Makes Ctrl-C interrupt the running evaluation, and exit the REPL otherwise.
+    pub fn install(handle: InterruptHandle) {
+        // Safety: the handler only touches atomics and exits, which is async-signal-safe.
+        let registered = unsafe {
+            low_level::register(SIGINT, move || {
+                if EVALUATING.load(Ordering::Relaxed) {
+                    handle.interrupt();
+                } else {
+                    // Exit status of a process killed by SIGINT.
+                    low_level::exit(128 + SIGINT);
+                }
+            })
+        };
+        if let Err(err) = registered {
+            eprintln!("cannot handle Ctrl-C: {err}");
+        }
+    }
+
+    pub fn set_evaluating(evaluating: bool) {
+        EVALUATING.store(evaluating, Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(unix))]
+mod sigint {
+    use monkey::object::error::InterruptHandle;
+
+    pub fn install(_: InterruptHandle) {}
+
+    pub fn set_evaluating(_: bool) {}
+}
@@ -0,0 +1,508 @@
+//! Arbitrary-precision integers, used when a result does not fit in `Object::INTEGER`.
+
+use std::{
+    cmp::Ordering,
+    fmt, mem,
+    ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub},
+};
+
+/// Sign and magnitude, stored as little endian base 2^32 limbs.
+///
+/// Values are kept normalized (no leading zero limbs, zero is never negative)
+/// so the derived `PartialEq` and `Hash` compare values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, magnitude: Vec<u32>) -> Self {
+        let magnitude = trim(magnitude);
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    /// Parses a non-empty string of decimal digits.
+    pub fn parse(digits: &str) -> Option<BigInt> {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut magnitude = vec![];
+        for chunk in digits.as_bytes().chunks(9) {
+            let value = chunk
+                .iter()
+                .fold(0, |value, digit| value * 10 + (digit - b'0') as u32);
+            mul_small(&mut magnitude, 10u32.pow(chunk.len() as u32));
+            add_small(&mut magnitude, value);
+        }
+        Some(BigInt::new(false, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    /// Nearest float, infinite when the value is out of range.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |value, limb| value * 4294967296.0 + *limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Number of bits of the magnitude.
+    pub fn bits(&self) -> usize {
+        self.magnitude.last().map_or(0, |top| {
+            self.magnitude.len() * 32 - top.leading_zeros() as usize
+        })
+    }
+
+    /// Bytes taken by the magnitude.
+    pub fn size(&self) -> usize {
+        self.magnitude.len() * mem::size_of::<u32>()
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    pub fn to_i32(&self) -> Option<i32> {
+        match self.magnitude.as_slice() {
+            [] => Some(0),
+            [limb] => {
+                let value = *limb as i64;
+                i32::try_from(if self.negative { -value } else { value }).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let abs = value.unsigned_abs();
+        BigInt::new(value < 0, vec![abs as u32, (abs >> 32) as u32])
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(value: i32) -> Self {
+        BigInt::from(value as i64)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude)
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::new(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::new(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other.clone()
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul_magnitude(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+/// Truncating division, like the primitive integers. Panics when dividing by zero.
+impl Div for &BigInt {
+    type Output = BigInt;
+
+    fn div(self, other: &BigInt) -> BigInt {
+        assert!(!other.is_zero(), "attempt to divide by zero");
+        let (quotient, _) = divmod_magnitude(&self.magnitude, &other.magnitude);
+        BigInt::new(self.negative != other.negative, quotient)
+    }
+}
+
+/// Remainder of the truncating division, it has the sign of the dividend.
+/// Panics when dividing by zero.
+impl Rem for &BigInt {
+    type Output = BigInt;
+
+    fn rem(self, other: &BigInt) -> BigInt {
+        assert!(
+            !other.is_zero(),
+            "attempt to calculate the remainder with a divisor of zero"
+        );
+        let (_, remainder) = divmod_magnitude(&self.magnitude, &other.magnitude);
+        BigInt::new(self.negative, remainder)
+    }
+}
+
+/// Bitwise operators behave as if values were stored in infinite two's complement.
+impl Not for &BigInt {
+    type Output = BigInt;
+
+    fn not(self) -> BigInt {
+        -(self + &BigInt::from(1))
+    }
+}
+
+impl BitAnd for &BigInt {
+    type Output = BigInt;
+
+    fn bitand(self, other: &BigInt) -> BigInt {
+        bitwise(self, other, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BigInt {
+    type Output = BigInt;
+
+    fn bitor(self, other: &BigInt) -> BigInt {
+        bitwise(self, other, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BigInt {
+    type Output = BigInt;
+
+    fn bitxor(self, other: &BigInt) -> BigInt {
+        bitwise(self, other, |a, b| a ^ b)
+    }
+}
+
+impl Shl<usize> for &BigInt {
+    type Output = BigInt;
+
+    fn shl(self, shift: usize) -> BigInt {
+        let mut magnitude = vec![0u32; shift / 32];
+        let bits = shift % 32;
+        let mut carry = 0u32;
+        for limb in &self.magnitude {
+            magnitude.push((limb << bits) | carry);
+            carry = if bits == 0 { 0 } else { limb >> (32 - bits) };
+        }
+        magnitude.push(carry);
+        BigInt::new(self.negative, magnitude)
+    }
+}
+
+/// Arithmetic shift, rounding towards negative infinity.
+impl Shr<usize> for &BigInt {
+    type Output = BigInt;
+
+    fn shr(self, shift: usize) -> BigInt {
+        if self.negative {
+            return !&(&!self >> shift);
+        }
+
+        let bits = shift % 32;
+        let limbs = self.magnitude.get(shift / 32..).unwrap_or(&[]);
+        let magnitude = limbs
+            .iter()
+            .enumerate()
+            .map(|(i, limb)| match limbs.get(i + 1) {
+                Some(next) if bits > 0 => (limb >> bits) | (next << (32 - bits)),
+                _ => limb >> bits,
+            })
+            .collect();
+        BigInt::new(false, magnitude)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Base 10^9 digits, least significant first.
+        let mut chunks = vec![];
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = divmod_small(&magnitude, 1_000_000_000);
+            chunks.push(remainder);
+            magnitude = quotient;
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        chunks
+            .iter()
+            .rev()
+            .try_for_each(|chunk| write!(f, "{chunk:09}"))
+    }
+}
+
+fn bitwise(a: &BigInt, b: &BigInt, op: impl Fn(u32, u32) -> u32) -> BigInt {
+    // One extra limb so the sign bit is never part of the magnitude.
+    let len = a.magnitude.len().max(b.magnitude.len()) + 1;
+    let (a, b) = (twos_complement(a, len), twos_complement(b, len));
+    let limbs: Vec<u32> = a.iter().zip(&b).map(|(x, y)| op(*x, *y)).collect();
+
+    if limbs[len - 1] >> 31 == 0 {
+        return BigInt::new(false, limbs);
+    }
+    let mut magnitude: Vec<u32> = limbs.iter().map(|limb| !limb).collect();
+    add_small(&mut magnitude, 1);
+    BigInt::new(true, magnitude)
+}
+
+fn twos_complement(value: &BigInt, len: usize) -> Vec<u32> {
+    let mut limbs = value.magnitude.clone();
+    limbs.resize(len, 0);
+    if value.negative {
+        limbs = sub_magnitude(&limbs, &[1]);
+        limbs.resize(len, 0);
+        limbs.iter_mut().for_each(|limb| *limb = !*limb);
+    }
+    limbs
+}
+
+fn trim(mut magnitude: Vec<u32>) -> Vec<u32> {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    magnitude
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, limb) in long.iter().enumerate() {
+        let sum = *limb as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    trim(result)
+}
+
+/// `a - b`, requires `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, limb) in a.iter().enumerate() {
+        let mut difference = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(result)
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let product = *x as u64 * *y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    trim(result)
+}
+
+fn mul_small(magnitude: &mut Vec<u32>, factor: u32) {
+    let mut carry = 0u64;
+    for limb in magnitude.iter_mut() {
+        let product = *limb as u64 * factor as u64 + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn add_small(magnitude: &mut Vec<u32>, value: u32) {
+    let mut carry = value as u64;
+    for limb in magnitude.iter_mut() {
+        if carry == 0 {
+            return;
+        }
+        let sum = *limb as u64 + carry;
+        *limb = sum as u32;
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn divmod_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for (i, limb) in a.iter().enumerate().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    (trim(quotient), remainder as u32)
+}
+
+/// Schoolbook long division, one limb of the quotient at a time (Knuth's algorithm D), in
+/// time proportional to the product of the lengths. `b` must not be zero.
+fn divmod_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [divisor] = b {
+        let (quotient, remainder) = divmod_small(a, *divisor);
+        return (quotient, trim(vec![remainder]));
+    }
+    if cmp_magnitude(a, b) == Ordering::Less {
+        return (vec![], a.to_vec());
+    }
+
+    // With the top bit of the divisor set, estimates of quotient limbs are off by at most 2.
+    let shift = b.last().unwrap().leading_zeros();
+    let b = shift_limbs_left(b, shift);
+    let mut u = shift_limbs_left(a, shift);
+    let n = b.len() - 1;
+    let (top, next) = (b[n - 1] as u64, b[n - 2] as u64);
+    let mut quotient = vec![0u32; a.len() - n + 1];
+
+    for j in (0..quotient.len()).rev() {
+        let current = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let (mut estimate, mut remainder) = (current / top, current % top);
+        while estimate >> 32 != 0 || estimate * next > (remainder << 32) | u[j + n - 2] as u64 {
+            estimate -= 1;
+            remainder += top;
+            if remainder >> 32 != 0 {
+                break;
+            }
+        }
+
+        // u[j..] -= estimate * b
+        let (mut carry, mut borrow) = (0u64, 0i64);
+        for i in 0..n {
+            let product = estimate * b[i] as u64 + carry;
+            carry = product >> 32;
+            let difference = u[i + j] as i64 - borrow - (product & 0xffff_ffff) as i64;
+            u[i + j] = difference as u32;
+            borrow = (difference < 0) as i64;
+        }
+        let difference = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = difference as u32;
+
+        // The estimate was one too large, add the divisor back.
+        if difference < 0 {
+            estimate -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + b[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+
+    u.truncate(n);
+    let remainder = u
+        .iter()
+        .zip(u.iter().skip(1).chain([&0]))
+        .map(|(low, high)| match shift {
+            0 => *low,
+            shift => (low >> shift) | (high << (32 - shift)),
+        })
+        .collect();
+    (trim(quotient), trim(remainder))
+}
+
+/// Shifts left by fewer than 32 bits, into one more limb.
+fn shift_limbs_left(magnitude: &[u32], shift: u32) -> Vec<u32> {
+    let mut shifted = Vec::with_capacity(magnitude.len() + 1);
+    let mut carry = 0u32;
+    for limb in magnitude {
+        shifted.push((limb << shift) | carry);
+        carry = if shift == 0 { 0 } else { limb >> (32 - shift) };
+    }
+    shifted.push(carry);
+    shifted
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+pub mod bigint_test {
+    use crate::object::bigint::BigInt;
+
+    fn big(digits: &str) -> BigInt {
+        match digits.strip_prefix('-') {
+            Some(digits) => -BigInt::parse(digits).unwrap(),
+            None => BigInt::parse(digits).unwrap(),
+        }
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let inputs = [
+            "0",
+            "7",
+            "4294967296",
+            "18446744073709551616",
+            "-1000000000000000000000000000001",
+            "123456789012345678901234567890123456789",
+        ];
+
+        inputs
+            .iter()
+            .for_each(|i| assert_eq!(big(i).to_string(), *i));
+        assert_eq!(big("000042").to_string(), "42");
+        assert_eq!(big("-0"), BigInt::from(0));
+        assert!(BigInt::parse("").is_none());
+        assert!(BigInt::parse("12a").is_none());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let input_expctdvalue = [
+            (&big("4294967295") + &big("1"), "4294967296"),
+            (&big("-5") + &big("3"), "-2"),
+            (&big("5") + &big("-5"), "0"),
+            (&big("4294967296") - &big("1"), "4294967295"),
+            (&big("3") - &big("10"), "-7"),
+            (&big("-3") - &big("-10"), "7"),
+            (
+                &big("4294967296") * &big("4294967296"),
+                "18446744073709551616",
+            ),
+            (
+                &big("-12345678901234567890") * &big("3"),
+                "-37037036703703703670",
+            ),
+            (
+                &big("18446744073709551616") / &big("4294967296"),
+                "4294967296",
+            ),
+            (
+                &big("-37037036703703703670") / &big("12345678901234567890"),
+                "-3",
+            ),
+            (&big("7") / &big("-2"), "-3"),
+            (
+                &big("18446744073709551615") / &big("18446744073709551616"),
+                "0",
+            ),
+            (
+                &big("340282366920938463463374607431768211457") / &big("18446744073709551617"),
+                "18446744073709551615",
+            ),
+        ];
+
+        input_expctdvalue
+            .iter()
+            .for_each(|(i, v)| assert_eq!(i.to_string(), *v));
+    }
+
+    #[test]
+    fn division_inverts_multiplication() {
+        // Pseudo-random operands of up to 8 limbs, with runs of set and cleared bits.
+        fn next(seed: &mut u64) -> u64 {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            *seed
+        }
+        fn random(seed: &mut u64) -> BigInt {
+            (0..next(seed) % 8 + 1).fold(BigInt::from(0), |value, _| {
+                let limb = match next(seed) % 4 {
+                    0 => 0,
+                    1 => u32::MAX as i64,
+                    _ => next(seed) as u32 as i64,
+                };
+                &(&value << 32) + &BigInt::from(limb)
+            })
+        }
+
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..500 {
+            let a = random(&mut seed);
+            let b = random(&mut seed);
+            if b.is_zero() {
+                continue;
+            }
+            let (quotient, remainder) = (&a / &b, &a % &b);
+            assert_eq!(&(&quotient * &b) + &remainder, a, "{a} / {b}");
+            assert!(remainder < b, "{a} % {b}");
+        }
+    }
+
+    #[test]
+    fn ordering_and_conversion() {
+        assert!(big("-18446744073709551616") < big("-4294967296"));
+        assert!(big("-1") < big("0"));
+        assert!(big("4294967296") > big("4294967295"));
+
+        assert_eq!(big("2147483647").to_i32(), Some(i32::MAX));
+        assert_eq!(big("-2147483648").to_i32(), Some(i32::MIN));
+        assert_eq!(big("2147483648").to_i32(), None);
+        assert_eq!(BigInt::from(i64::MIN).to_string(), "-9223372036854775808");
+    }
+
+    #[test]
+    fn bitwise_matches_primitive_integers() {
+        let values: [i64; 8] = [0, 1, -1, 12, -12, 4294967295, -4294967296, 1 << 40];
+
+        for a in values {
+            for b in values {
+                let (x, y) = (BigInt::from(a), BigInt::from(b));
+                assert_eq!(&x & &y, BigInt::from(a & b), "{a} & {b}");
+                assert_eq!(&x | &y, BigInt::from(a | b), "{a} | {b}");
+                assert_eq!(&x ^ &y, BigInt::from(a ^ b), "{a} ^ {b}");
+                if b != 0 {
+                    assert_eq!(&x % &y, BigInt::from(a % b), "{a} % {b}");
+                }
+            }
+            assert_eq!(!&BigInt::from(a), BigInt::from(!a), "~{a}");
+            for shift in [0, 1, 31, 32, 33, 63, 64, 100] {
+                let expected = a >> shift.min(63);
+                assert_eq!(
+                    &BigInt::from(a) >> shift,
+                    BigInt::from(expected),
+                    "{a} >> {shift}"
+                );
+            }
+            for shift in [0, 1, 7, 20] {
+                assert_eq!(
+                    &BigInt::from(a) << shift,
+                    BigInt::from(a << shift),
+                    "{a} << {shift}"
+                );
+            }
+        }
+
+        assert_eq!(big("3").pow(40).to_string(), "12157665459056928801");
+        assert_eq!(big("-2").pow(63), BigInt::from(i64::MIN));
+        assert_eq!(big("18446744073709551616").bits(), 65);
+    }
+}
@@ -1,4 +1,6 @@
-use super::object::Object;
+use std::mem;
+
+use super::{error::ErrorKind, object::Object};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BuiltinFunction {
@@ -25,6 +27,15 @@ impl BuiltinFunction {
         }
     }
 
+    /// Approximate number of bytes allocated by the call that returned `result`.
+    pub fn allocated(&self, result: &Object) -> usize {
+        match (self, result) {
+            // The new array shares everything but the pushed element with the original one.
+            (BuiltinFunction::PUSH, Object::ARRAY(_)) => mem::size_of::<Object>(),
+            _ => 0,
+        }
+    }
+
     pub fn call(&self, args: Vec<Object>) -> Object {
         match self {
             BuiltinFunction::LEN => Self::call_len(args),
@@ -37,48 +48,70 @@ impl BuiltinFunction {
 
     fn call_len(args: Vec<Object>) -> Object {
         Self::handle_expected_number_arguments(1, args.len()).unwrap_or_else(|| match &args[0] {
-            Object::STRING(s) => Object::INTEGER(s.len().try_into().unwrap()),
-            Object::ARRAY(a) => Object::INTEGER(a.len().try_into().unwrap()),
-            _ => Object::ERROR("Argument type not supported by `len`.".to_string()),
+            Object::STRING(s) => Self::length(s.chars().count()),
+            Object::ARRAY(a) => Self::length(a.borrow().len()),
+            _ => Object::error(
+                ErrorKind::InvalidArgument,
+                "Argument type not supported by `len`.",
+            ),
         })
     }
 
+    fn length(len: usize) -> Object {
+        i32::try_from(len).map_or_else(
+            |_| {
+                Object::error(
+                    ErrorKind::IntegerOverflow,
+                    format!("integer overflow: {len}"),
+                )
+            },
+            Object::INTEGER,
+        )
+    }
+
     fn call_first(args: Vec<Object>) -> Object {
         Self::handle_expected_number_arguments(1, args.len()).unwrap_or_else(|| match &args[0] {
-            Object::ARRAY(a) => a.first().unwrap_or(&Object::NULL).clone(),
-            _ => Object::ERROR("Argument type not supported by `first`.".to_string()),
+            Object::ARRAY(a) => a.borrow().first().unwrap_or(&Object::NULL).clone(),
+            _ => Object::error(
+                ErrorKind::InvalidArgument,
+                "Argument type not supported by `first`.",
+            ),
         })
     }
 
     fn call_last(args: Vec<Object>) -> Object {
         Self::handle_expected_number_arguments(1, args.len()).unwrap_or_else(|| match &args[0] {
-            Object::ARRAY(a) => a.last().unwrap_or(&Object::NULL).clone(),
-            _ => Object::ERROR("Argument type not supported by `last`.".to_string()),
+            Object::ARRAY(a) => a.borrow().last().unwrap_or(&Object::NULL).clone(),
+            _ => Object::error(
+                ErrorKind::InvalidArgument,
+                "Argument type not supported by `last`.",
+            ),
         })
     }
 
     fn call_tail(args: Vec<Object>) -> Object {
         Self::handle_expected_number_arguments(1, args.len()).unwrap_or_else(|| match &args[0] {
-            Object::ARRAY(a) => {
-                if a.len() > 0 {
-                    Object::ARRAY(a[1..].to_vec())
-                } else {
-                    Object::NULL
-                }
-            }
-            _ => Object::ERROR("Argument type not supported by `tail`.".to_string()),
+            Object::ARRAY(a) => a.borrow().tail().map_or(Object::NULL, Object::array),
+            _ => Object::error(
+                ErrorKind::InvalidArgument,
+                "Argument type not supported by `tail`.",
+            ),
         })
     }
 
     fn call_push(args: Vec<Object>) -> Object {
         Self::handle_expected_number_arguments(2, args.len()).unwrap_or_else(|| {
             match (&args[0], &args[1]) {
+                // The array passed in is left unchanged, the result shares its elements.
                 (Object::ARRAY(a), obj) => {
-                    let mut b = a.clone();
-                    b.push(obj.clone());
-                    Object::ARRAY(b)
+                    let mut elements = a.borrow().clone();
+                    elements.push(obj.clone());
+                    Object::array(elements)
                 }
-                _ => Object::ERROR("Argument type not supported by `last`.".to_string()),
+                _ => Object::error(
+                    ErrorKind::InvalidArgument,
+                    "Argument type not supported by `last`.",
+                ),
             }
         })
     }
@@ -86,9 +119,10 @@ impl BuiltinFunction {
     fn handle_expected_number_arguments(expected: usize, received: usize) -> Option<Object> {
         match expected == received {
             true => None,
-            false => Some(Object::ERROR(format!(
-                "Wrong number of arguments. Expected: {expected} | Got: {received}"
-            ))),
+            false => Some(Object::error(
+                ErrorKind::WrongArgumentCount,
+                format!("Wrong number of arguments. Expected: {expected} | Got: {received}"),
+            )),
         }
     }
 }
@@ -2,12 +2,18 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use super::object::Object;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Environment {
     store: HashMap<String, Object>,
     outer: Option<Rc<RefCell<Environment>>>,
 }
 
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Environment {
     pub fn new() -> Self {
         Environment {
@@ -26,7 +32,7 @@ impl Environment {
     pub fn get(&self, name: &str) -> Option<Object> {
         match self.store.get(name) {
             None => match &self.outer {
-                Some(e) => e.borrow().get(&name),
+                Some(e) => e.borrow().get(name),
                 None => None,
             },
             Some(get) => Some(get.clone()),
@@ -36,4 +42,33 @@ impl Environment {
     pub fn set(&mut self, name: String, value: Object) {
         self.store.insert(name, value);
     }
+
+    /// Values of the bindings, for the cycle collector.
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Object> {
+        self.store.values()
+    }
+
+    pub(crate) fn outer(&self) -> Option<&Rc<RefCell<Environment>>> {
+        self.outer.as_ref()
+    }
+
+    /// Drops every binding and the enclosing environment.
+    pub(crate) fn clear(&mut self) {
+        self.store.clear();
+        self.outer = None;
+    }
+
+    /// Updates the innermost existing binding of `name`, returns `false` when there is none.
+    pub fn assign(&mut self, name: &str, value: Object) -> bool {
+        match self.store.get_mut(name) {
+            Some(binding) => {
+                *binding = value;
+                true
+            }
+            None => match &self.outer {
+                Some(e) => e.borrow_mut().assign(name, value),
+                None => false,
+            },
+        }
+    }
 }
@@ -0,0 +1,128 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::token::token::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    TypeMismatch,
+    UnknownOperator,
+    UnknownIdentifier,
+    NotCallable,
+    WrongArgumentCount,
+    InvalidArgument,
+    /// Operand outside of the domain of an operator, like a negative shift count.
+    InvalidOperand,
+    UnusableHashKey,
+    UnsupportedIndex,
+    IndexOutOfBounds,
+    NotIterable,
+    IntegerOverflow,
+    /// Float result too large to be represented.
+    FloatOverflow,
+    DivisionByZero,
+    /// More nested calls than the backend allows.
+    StackOverflow,
+    /// The step or time limit of the `Evaluator` ran out.
+    BudgetExhausted,
+    Interrupted,
+    /// The allocation budget of the backend ran out.
+    OutOfMemory,
+}
+
+/// Aborts the running evaluation of a backend with an `Interrupted` error. It can be used from
+/// other threads and from signal handlers.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Discards a pending interrupt, one received after the evaluation it was meant for ended.
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Consumes a pending interrupt, which aborts the evaluation polling it.
+    pub(crate) fn check(&self) -> Result<(), RuntimeError> {
+        if self.0.swap(false, Ordering::Relaxed) {
+            return Err(RuntimeError::new(ErrorKind::Interrupted, "interrupted"));
+        }
+        Ok(())
+    }
+}
+
+/// A function call that was active when an error was raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: Span,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in {}, called at {}", self.function, self.call_site)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Option<Span>,
+    /// Active calls, outermost first.
+    pub stack: Vec<Frame>,
+}
+
+impl RuntimeError {
+    pub fn new<S: Into<String>>(kind: ErrorKind, message: S) -> Self {
+        RuntimeError {
+            kind,
+            message: message.into(),
+            span: None,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn stack_overflow(max_depth: usize) -> Self {
+        RuntimeError::new(
+            ErrorKind::StackOverflow,
+            format!("stack overflow: more than {max_depth} nested calls"),
+        )
+    }
+
+    pub fn steps_exhausted(limit: u64) -> Self {
+        RuntimeError::new(
+            ErrorKind::BudgetExhausted,
+            format!("budget exhausted: more than {limit} steps"),
+        )
+    }
+
+    pub fn time_exhausted(limit: Duration) -> Self {
+        RuntimeError::new(
+            ErrorKind::BudgetExhausted,
+            format!("budget exhausted: time limit of {limit:?} reached"),
+        )
+    }
+
+    pub fn out_of_memory(limit: usize) -> Self {
+        RuntimeError::new(
+            ErrorKind::OutOfMemory,
+            format!("out of memory: more than {limit} bytes allocated"),
+        )
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
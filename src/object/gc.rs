@@ -0,0 +1,279 @@
+//! Cycle collector for environments. `Rc` frees values once nothing refers to them, but a
+//! function stored in the environment it captures, like every recursive `let f = fn...`,
+//! keeps both alive forever.
+//!
+//! A collection looks at the environments, arrays, hashes and vector nodes reachable from the
+//! tracked environments. References counted by `Rc` but not held by one of these come from
+//! outside, the backends or the host, and keep alive everything reachable from them. The
+//! rest is only referenced by itself and is cleared, which breaks its cycles.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::{Rc, Weak},
+};
+
+use super::{
+    env::Environment,
+    object::{HashKey, HashPair, Object},
+    vector::{self, Vector},
+};
+
+/// Tracked environments triggering the first collection.
+const MIN_THRESHOLD: usize = 1024;
+
+pub struct Collector {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    /// Tracked environments triggering the next collection, twice the values traced through
+    /// what survived the previous one. A collection takes time proportional to these, so it
+    /// is paid for by as many environments created in the meantime.
+    threshold: usize,
+    collections: usize,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Collector {
+            environments: Vec::new(),
+            threshold: MIN_THRESHOLD,
+            collections: 0,
+        }
+    }
+
+    /// Allocates `env`, collecting first when enough environments were tracked since the
+    /// previous collection. Values held outside of tracked environments are never freed, so
+    /// this can be called at any point of an evaluation.
+    pub fn track(&mut self, env: Environment) -> Rc<RefCell<Environment>> {
+        if self.environments.len() >= self.threshold {
+            self.collect();
+        }
+        let env = Rc::new(RefCell::new(env));
+        self.environments.push(Rc::downgrade(&env));
+        env
+    }
+
+    /// Number of tracked environments still alive.
+    pub fn len(&self) -> usize {
+        self.environments
+            .iter()
+            .filter(|env| env.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of collections run so far.
+    pub fn collections(&self) -> usize {
+        self.collections
+    }
+
+    /// Clears the environments only kept alive by cycles, returning how many.
+    pub fn collect(&mut self) -> usize {
+        self.environments.retain(|env| env.strong_count() > 0);
+        let mut graph = Graph::default();
+        for env in &self.environments {
+            if let Some(env) = env.upgrade() {
+                graph.add(Node::Environment(env));
+            }
+        }
+        graph.discover();
+
+        let garbage = graph.garbage();
+        let traced: usize = graph.nodes.iter().map(Node::size).sum();
+        let live = traced - garbage.iter().map(Node::size).sum::<usize>();
+        let mut freed = 0;
+        for node in garbage {
+            if let Node::Environment(_) = node {
+                freed += 1;
+            }
+            node.clear();
+        }
+        drop(graph);
+
+        self.environments.retain(|env| env.strong_count() > 0);
+        self.threshold = MIN_THRESHOLD.max(2 * live);
+        self.collections += 1;
+        freed
+    }
+}
+
+#[derive(Clone)]
+enum Node {
+    Environment(Rc<RefCell<Environment>>),
+    Array(Rc<RefCell<Vector<Object>>>),
+    Hash(Rc<RefCell<BTreeMap<HashKey, HashPair>>>),
+    Trie(Rc<vector::Node<Object>>),
+}
+
+impl Node {
+    fn address(&self) -> *const () {
+        match self {
+            Node::Environment(env) => Rc::as_ptr(env) as *const (),
+            Node::Array(array) => Rc::as_ptr(array) as *const (),
+            Node::Hash(hash) => Rc::as_ptr(hash) as *const (),
+            Node::Trie(node) => Rc::as_ptr(node) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Environment(env) => Rc::strong_count(env),
+            Node::Array(array) => Rc::strong_count(array),
+            Node::Hash(hash) => Rc::strong_count(hash),
+            Node::Trie(node) => Rc::strong_count(node),
+        }
+    }
+
+    /// Values held by this node, which tracing it goes through. Each environment counts at
+    /// least one, so the survivors of a collection are part of it too.
+    fn size(&self) -> usize {
+        match self {
+            Node::Environment(env) => env.try_borrow().map_or(1, |env| env.values().count() + 1),
+            Node::Array(_) => 1,
+            Node::Hash(hash) => hash.try_borrow().map_or(1, |hash| 2 * hash.len() + 1),
+            Node::Trie(node) => match node.as_ref() {
+                vector::Node::Branch(children) => children.len(),
+                vector::Node::Leaf(values) => values.len(),
+            },
+        }
+    }
+
+    /// Calls `f` with every node this one refers to. Returns false when it is borrowed, its
+    /// references are unknown then.
+    fn references(&self, f: &mut impl FnMut(Node)) -> bool {
+        match self {
+            Node::Environment(env) => {
+                let Ok(env) = env.try_borrow() else {
+                    return false;
+                };
+                if let Some(outer) = env.outer() {
+                    f(Node::Environment(Rc::clone(outer)));
+                }
+                env.values().for_each(|value| object_references(value, f));
+            }
+            Node::Array(array) => {
+                let Ok(array) = array.try_borrow() else {
+                    return false;
+                };
+                f(Node::Trie(Rc::clone(array.root())));
+            }
+            Node::Hash(hash) => {
+                let Ok(hash) = hash.try_borrow() else {
+                    return false;
+                };
+                for pair in hash.values() {
+                    object_references(&pair.key, f);
+                    object_references(&pair.value, f);
+                }
+            }
+            Node::Trie(node) => match node.as_ref() {
+                vector::Node::Branch(children) => children
+                    .iter()
+                    .for_each(|child| f(Node::Trie(Rc::clone(child)))),
+                // Elements dropped by `tail` are still held by the leaves.
+                vector::Node::Leaf(values) => {
+                    values.iter().for_each(|value| object_references(value, f))
+                }
+            },
+        }
+        true
+    }
+
+    fn clear(&self) {
+        match self {
+            Node::Environment(env) => env.borrow_mut().clear(),
+            Node::Array(array) => *array.borrow_mut() = Vector::new(),
+            Node::Hash(hash) => hash.borrow_mut().clear(),
+            // Shared with other vectors, the arrays holding it are cleared instead.
+            Node::Trie(_) => (),
+        }
+    }
+}
+
+fn object_references(object: &Object, f: &mut impl FnMut(Node)) {
+    match object {
+        Object::FUNCTION(function) => f(Node::Environment(Rc::clone(&function.env))),
+        Object::CLOSURE(closure) => f(Node::Environment(Rc::clone(&closure.env))),
+        Object::ARRAY(array) => f(Node::Array(Rc::clone(array))),
+        Object::HASH(hash) => f(Node::Hash(Rc::clone(hash))),
+        Object::RETURN(value) => object_references(value, f),
+        Object::TAILCALL(call) => {
+            f(Node::Environment(Rc::clone(&call.function.env)));
+            call.arguments
+                .iter()
+                .for_each(|argument| object_references(argument, f));
+        }
+        _ => (),
+    }
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    indexes: HashMap<*const (), usize>,
+}
+
+impl Graph {
+    fn add(&mut self, node: Node) {
+        if !self.indexes.contains_key(&node.address()) {
+            self.indexes.insert(node.address(), self.nodes.len());
+            self.nodes.push(node);
+        }
+    }
+
+    /// Adds every node reachable from the ones already added.
+    fn discover(&mut self) {
+        let mut index = 0;
+        while index < self.nodes.len() {
+            let node = self.nodes[index].clone();
+            node.references(&mut |reference| self.add(reference));
+            index += 1;
+        }
+    }
+
+    /// Nodes unreachable from references held outside of the graph.
+    fn garbage(&self) -> Vec<Node> {
+        // The graph holds one reference to each node.
+        let mut external: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|node| node.strong_count() - 1)
+            .collect();
+        let mut reachable = vec![false; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            // A borrowed node is in use, and the nodes it refers to keep their references.
+            reachable[index] = !node.references(&mut |reference| {
+                external[self.indexes[&reference.address()]] -= 1;
+            });
+        }
+
+        let mut pending: Vec<usize> = (0..self.nodes.len())
+            .filter(|&index| reachable[index] || external[index] > 0)
+            .collect();
+        pending.iter().for_each(|&index| reachable[index] = true);
+        while let Some(index) = pending.pop() {
+            self.nodes[index].references(&mut |reference| {
+                let index = self.indexes[&reference.address()];
+                if !reachable[index] {
+                    reachable[index] = true;
+                    pending.push(index);
+                }
+            });
+        }
+
+        self.nodes
+            .iter()
+            .zip(reachable)
+            .filter(|(_, reachable)| !reachable)
+            .map(|(node, _)| node.clone())
+            .collect()
+    }
+}
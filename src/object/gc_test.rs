@@ -0,0 +1,86 @@
+#[cfg(test)]
+pub mod gc_test {
+    use std::rc::Rc;
+
+    use crate::object::{
+        env::Environment,
+        gc::Collector,
+        object::{Function, Object},
+    };
+
+    fn function(env: &Rc<std::cell::RefCell<Environment>>) -> Object {
+        Object::FUNCTION(Function {
+            parameters: Vec::new(),
+            body: Rc::new(Vec::new()),
+            env: Rc::clone(env),
+        })
+    }
+
+    #[test]
+    fn collects_cycles() {
+        let mut collector = Collector::new();
+        let global = collector.track(Environment::new());
+
+        // A function stored in the environment it captures, and one stored in an array.
+        let recursive = collector.track(Environment::new_enclosed(Rc::clone(&global)));
+        let f = function(&recursive);
+        recursive.borrow_mut().set("f".to_string(), f);
+        let nested = collector.track(Environment::new_enclosed(Rc::clone(&global)));
+        let array = Object::array(vec![Object::INTEGER(1), function(&nested)]);
+        nested.borrow_mut().set("a".to_string(), array);
+
+        // Referred to by the host, and by the global environment.
+        let held = collector.track(Environment::new_enclosed(Rc::clone(&global)));
+        held.borrow_mut().set("g".to_string(), function(&held));
+        let bound = collector.track(Environment::new_enclosed(Rc::clone(&global)));
+        bound.borrow_mut().set("h".to_string(), function(&bound));
+        let h = function(&bound);
+        global.borrow_mut().set("h".to_string(), h);
+
+        let weak = (Rc::downgrade(&recursive), Rc::downgrade(&nested));
+        drop((recursive, nested));
+        let (recursive, nested) = weak;
+        assert_eq!(collector.len(), 5);
+        assert_eq!(collector.collect(), 2);
+        assert!(recursive.upgrade().is_none());
+        assert!(nested.upgrade().is_none());
+        assert!(held.borrow().get("g").is_some());
+        assert!(bound.borrow().get("h").is_some());
+        assert_eq!(collector.len(), 3);
+
+        drop((held, bound));
+        assert_eq!(collector.collect(), 1);
+        drop(global);
+        assert_eq!(collector.collect(), 2);
+        assert!(collector.is_empty());
+    }
+
+    #[test]
+    fn collects_as_environments_are_tracked() {
+        let mut collector = Collector::new();
+        for _ in 0..10000 {
+            let env = collector.track(Environment::new());
+            env.borrow_mut().set("f".to_string(), function(&env));
+        }
+        assert!(collector.len() <= 1024);
+    }
+
+    #[test]
+    fn scales_with_live_values() {
+        // Every collection traces the large array, so they must not run at a fixed pace.
+        let mut collector = Collector::new();
+        let global = collector.track(Environment::new());
+        let array = Object::array((0..100_000).map(Object::INTEGER).collect::<Vec<_>>());
+        global.borrow_mut().set("a".to_string(), array);
+
+        for _ in 0..100_000 {
+            let env = collector.track(Environment::new_enclosed(Rc::clone(&global)));
+            env.borrow_mut().set("f".to_string(), function(&env));
+        }
+        assert!(collector.collections() <= 2);
+
+        let leaked = collector.len() - 1;
+        assert_eq!(collector.collect(), leaked);
+        assert!(global.borrow().get("a").is_some());
+    }
+}
@@ -0,0 +1,73 @@
+//! Iteration over the collections accepted by `for` loops, shared by both backends.
+
+use std::vec;
+
+use super::{
+    error::{ErrorKind, RuntimeError},
+    object::Object,
+    vector,
+};
+
+pub enum ObjectIterator {
+    Array(vector::IntoIter<Object>),
+    /// Keys of a hash, in key order.
+    Keys(vec::IntoIter<Object>),
+    /// Characters of a string, `index` is the byte offset of the next one.
+    Chars {
+        string: String,
+        index: usize,
+    },
+    /// Integers from `next` up to, and excluding, `end`.
+    Range {
+        next: i32,
+        end: i32,
+    },
+}
+
+impl ObjectIterator {
+    pub fn new(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            // Loops go over the elements present when they start, even if the body changes them.
+            Object::ARRAY(elements) => {
+                Ok(ObjectIterator::Array(elements.borrow().clone().into_iter()))
+            }
+            Object::HASH(hash) => {
+                let keys: Vec<Object> = hash
+                    .borrow()
+                    .values()
+                    .map(|pair| pair.key.clone())
+                    .collect();
+                Ok(ObjectIterator::Keys(keys.into_iter()))
+            }
+            Object::STRING(string) => Ok(ObjectIterator::Chars { string, index: 0 }),
+            Object::RANGE(start, end) => Ok(ObjectIterator::Range { next: start, end }),
+            obj => Err(RuntimeError::new(
+                ErrorKind::NotIterable,
+                format!("not iterable: {}", obj.get_type()),
+            )),
+        }
+    }
+}
+
+impl Iterator for ObjectIterator {
+    type Item = Object;
+
+    fn next(&mut self) -> Option<Object> {
+        match self {
+            ObjectIterator::Array(elements) => elements.next(),
+            ObjectIterator::Keys(keys) => keys.next(),
+            ObjectIterator::Chars { string, index } => {
+                let c = string[*index..].chars().next()?;
+                *index += c.len_utf8();
+                Some(Object::STRING(c.to_string()))
+            }
+            ObjectIterator::Range { next, end } => {
+                if *next >= *end {
+                    return None;
+                }
+                *next += 1;
+                Some(Object::INTEGER(*next - 1))
+            }
+        }
+    }
+}
@@ -1,3 +1,12 @@
+pub mod bigint;
+pub mod bigint_test;
+pub mod builtin;
 pub mod env;
+pub mod error;
+pub mod gc;
+pub mod gc_test;
+pub mod iterator;
 pub mod object;
-pub mod builtin;
+pub mod ops;
+pub mod vector;
+pub mod vector_test;
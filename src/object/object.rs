@@ -1,51 +1,200 @@
-use super::{builtin::BuiltinFunction, env::Environment};
-use crate::ast::ast::{Identifier, Statement};
-use std::{cell::RefCell, fmt, rc::Rc};
+use super::{
+    bigint::BigInt,
+    builtin::BuiltinFunction,
+    env::Environment,
+    error::{ErrorKind, Frame, RuntimeError},
+    vector::Vector,
+};
+use crate::{
+    ast::ast::{Identifier, Statement},
+    compiler::code::CompiledFunction,
+};
+use std::{cell::RefCell, cmp::Ordering, collections::BTreeMap, fmt, mem, rc::Rc};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Object {
     INTEGER(i32),
+    /// Integer outside the range of `INTEGER`, never holds a value that would fit in it.
+    BIGINT(BigInt),
+    FLOAT(f64),
     BOOLEAN(bool),
     STRING(String),
     NULL,
     RETURN(Box<Object>),
-    ERROR(String),
+    /// Produced by `break` and `continue`, unwinds the statements up to the enclosing loop.
+    BREAK,
+    CONTINUE,
+    /// Call in tail position, unwinds to the caller's `apply_function` which runs it in place
+    /// of the caller.
+    TAILCALL(Box<TailCall>),
+    ERROR(RuntimeError),
     FUNCTION(Function),
+    CLOSURE(Closure),
     BUILTIN(BuiltinFunction),
-    ARRAY(Vec<Object>),
+    /// Arrays and hashes are shared: copies of the object refer to the same collection.
+    ARRAY(Rc<RefCell<Vector<Object>>>),
+    HASH(Rc<RefCell<BTreeMap<HashKey, HashPair>>>),
+    /// Integers from the first bound up to, and excluding, the second one, produced by `..`.
+    RANGE(i32, i32),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Function {
     pub parameters: Vec<Identifier>,
-    pub body: Vec<Statement>,
+    /// Shared by the copies of the function, so calls do not copy the AST.
+    pub body: Rc<Vec<Statement>>,
     pub env: Rc<RefCell<Environment>>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct TailCall {
+    pub function: Function,
+    pub arguments: Vec<Object>,
+    pub frame: Frame,
+}
+
+/// Function produced by the `Vm`, pairing compiled code with the environment it was created in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+/// Key of a `HASH` entry. Only integers, booleans and strings can be used as keys.
+///
+/// Integers are ordered numerically whether or not they fit in an `INTEGER`, then come
+/// booleans and strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    INTEGER(i32),
+    BIGINT(BigInt),
+    BOOLEAN(bool),
+    STRING(String),
+}
+
+impl HashKey {
+    fn rank(&self) -> u8 {
+        match self {
+            HashKey::INTEGER(_) | HashKey::BIGINT(_) => 0,
+            HashKey::BOOLEAN(_) => 1,
+            HashKey::STRING(_) => 2,
+        }
+    }
+}
+
+impl Ord for HashKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (HashKey::INTEGER(a), HashKey::INTEGER(b)) => a.cmp(b),
+            (HashKey::INTEGER(a), HashKey::BIGINT(b)) => BigInt::from(*a).cmp(b),
+            (HashKey::BIGINT(a), HashKey::INTEGER(b)) => a.cmp(&BigInt::from(*b)),
+            (HashKey::BIGINT(a), HashKey::BIGINT(b)) => a.cmp(b),
+            (HashKey::BOOLEAN(a), HashKey::BOOLEAN(b)) => a.cmp(b),
+            (HashKey::STRING(a), HashKey::STRING(b)) => a.cmp(b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+impl PartialOrd for HashKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Entry of a `HASH`, keeping the original key object alongside its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+impl HashPair {
+    /// Approximate number of bytes taken by an entry of a `HASH`.
+    pub const SIZE: usize = mem::size_of::<HashKey>() + mem::size_of::<HashPair>();
+}
+
 impl Object {
+    pub fn error<S: Into<String>>(kind: ErrorKind, message: S) -> Object {
+        Object::ERROR(RuntimeError::new(kind, message))
+    }
+
+    /// Integer object for `value`, demoted to `INTEGER` when it fits.
+    pub fn integer(value: BigInt) -> Object {
+        match value.to_i32() {
+            Some(i) => Object::INTEGER(i),
+            None => Object::BIGINT(value),
+        }
+    }
+
+    pub fn array<V: Into<Vector<Object>>>(elements: V) -> Object {
+        Object::ARRAY(Rc::new(RefCell::new(elements.into())))
+    }
+
+    pub fn hash(pairs: BTreeMap<HashKey, HashPair>) -> Object {
+        Object::HASH(Rc::new(RefCell::new(pairs)))
+    }
+
     pub fn get_type(&self) -> String {
         match self {
-            Object::INTEGER(_) => "INTEGER".to_string(),
+            Object::INTEGER(_) | Object::BIGINT(_) => "INTEGER".to_string(),
+            Object::FLOAT(_) => "FLOAT".to_string(),
             Object::BOOLEAN(_) => "BOOLEAN".to_string(),
             Object::STRING(_) => "STRING".to_string(),
             Object::NULL => "NULL".to_string(),
             Object::RETURN(obj) => obj.get_type(),
+            Object::BREAK => "BREAK".to_string(),
+            Object::CONTINUE => "CONTINUE".to_string(),
+            Object::TAILCALL(_) => "TAILCALL".to_string(),
             Object::ERROR(_) => "ERROR".to_string(),
-            Object::FUNCTION(_) => "FUNCTION".to_string(),
+            Object::FUNCTION(_) | Object::CLOSURE(_) => "FUNCTION".to_string(),
             Object::BUILTIN(_) => "BUILTIN".to_string(),
             Object::ARRAY(_) => "ARRAY".to_string(),
+            Object::HASH(_) => "HASH".to_string(),
+            Object::RANGE(..) => "RANGE".to_string(),
+        }
+    }
+
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Object::INTEGER(i) => Some(HashKey::INTEGER(*i)),
+            Object::BIGINT(b) => Some(HashKey::BIGINT(b.clone())),
+            Object::BOOLEAN(b) => Some(HashKey::BOOLEAN(*b)),
+            Object::STRING(s) => Some(HashKey::STRING(s.clone())),
+            _ => None,
         }
     }
 }
 
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_nested(f, &mut Vec::new())
+    }
+}
+
+impl Object {
+    /// `enclosing` holds the collections being displayed, a collection containing itself is
+    /// shown as `[...]` or `{...}` instead of recursing forever.
+    fn fmt_nested(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        enclosing: &mut Vec<*const ()>,
+    ) -> fmt::Result {
         match self {
             Object::INTEGER(x) => write!(f, "{x}"),
+            Object::BIGINT(x) => write!(f, "{x}"),
+            // `Debug` is the shortest representation that parses back to the same value.
+            Object::FLOAT(x) => write!(f, "{x:?}"),
             Object::BOOLEAN(b) => write!(f, "{b}"),
             Object::STRING(s) => write!(f, "{s}"),
             Object::NULL => write!(f, "null"),
-            Object::RETURN(r) => write!(f, "return {r}"),
+            Object::RETURN(r) => {
+                write!(f, "return ")?;
+                r.fmt_nested(f, enclosing)
+            }
+            Object::BREAK => write!(f, "break"),
+            Object::CONTINUE => write!(f, "continue"),
+            Object::TAILCALL(call) => write!(f, "tail call to {}", call.frame.function),
             Object::ERROR(s) => write!(f, "{s}"),
             Object::FUNCTION(fun) => write!(
                 f,
@@ -57,15 +206,48 @@ impl fmt::Display for Object {
                     .join(", "),
                 fun.body
             ),
-            Object::BUILTIN(_) => write!(f, "builtin"),
-            Object::ARRAY(v) => write!(
+            Object::CLOSURE(closure) => write!(
                 f,
-                "[{}]",
-                v.iter()
-                    .map(|f| format!("{f}"))
-                    .collect::<Vec<String>>()
-                    .join(", ")
+                "fn ({}) {{\n{}}}\n",
+                closure.function.parameters.join(", "),
+                closure.function
             ),
+            Object::BUILTIN(_) => write!(f, "builtin"),
+            Object::ARRAY(a) => {
+                let address = Rc::as_ptr(a) as *const ();
+                if enclosing.contains(&address) {
+                    return write!(f, "[...]");
+                }
+                enclosing.push(address);
+                write!(f, "[")?;
+                for (i, element) in a.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.fmt_nested(f, enclosing)?;
+                }
+                enclosing.pop();
+                write!(f, "]")
+            }
+            Object::HASH(h) => {
+                let address = Rc::as_ptr(h) as *const ();
+                if enclosing.contains(&address) {
+                    return write!(f, "{{...}}");
+                }
+                enclosing.push(address);
+                write!(f, "{{")?;
+                for (i, pair) in h.borrow().values().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    pair.key.fmt_nested(f, enclosing)?;
+                    write!(f, ": ")?;
+                    pair.value.fmt_nested(f, enclosing)?;
+                }
+                enclosing.pop();
+                write!(f, "}}")
+            }
+            Object::RANGE(start, end) => write!(f, "{start}..{end}"),
         }
     }
 }
@@ -0,0 +1,444 @@
+//! Operator semantics shared by the tree-walking evaluator and the virtual machine,
+//! so that both backends produce the same objects and the same errors.
+
+use std::{collections::BTreeMap, fmt};
+
+use super::{
+    bigint::BigInt,
+    error::ErrorKind,
+    object::{HashPair, Object},
+    vector::Vector,
+};
+use crate::token::token::Token;
+
+const TRUE: Object = Object::BOOLEAN(true);
+const FALSE: Object = Object::BOOLEAN(false);
+const NULL: Object = Object::NULL;
+
+/// Largest integer, in bits, that `*`, `**` and `<<` may produce. It also bounds the time
+/// taken by the quadratic multiplication and division.
+const MAX_INTEGER_BITS: usize = 1 << 20;
+
+pub fn is_truthy(object: &Object) -> bool {
+    match *object {
+        FALSE | Object::NULL | Object::INTEGER(0) => false,
+        Object::FLOAT(f) => f != 0.0,
+        _ => true,
+    }
+}
+
+pub fn eval_prefix_expression(operator: &Token, object: Object) -> Object {
+    match operator {
+        Token::BANG => eval_bang_expression(object),
+        Token::SUB => eval_minus_expression(object),
+        Token::TILDE => eval_bitwise_not_expression(object),
+        _ => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: {}{}", operator, object.get_type()),
+        ),
+    }
+}
+
+pub fn eval_infix_expression(
+    operator: &Token,
+    object_left: Object,
+    object_right: Object,
+) -> Object {
+    match (object_left, object_right) {
+        (a, b) if *operator == Token::DOTDOT => eval_range_expression(a, b),
+        (Object::INTEGER(a), Object::INTEGER(b)) => eval_integer_infix_expression(operator, a, b),
+        (
+            a @ (Object::INTEGER(_) | Object::BIGINT(_)),
+            b @ (Object::INTEGER(_) | Object::BIGINT(_)),
+        ) => eval_big_integer_infix_expression(operator, to_big_integer(a), to_big_integer(b)),
+        (Object::BOOLEAN(a), Object::BOOLEAN(b)) => eval_boolean_infix_expression(operator, a, b),
+        (Object::STRING(a), Object::STRING(b)) => eval_string_infix_expression(operator, a, b),
+        (Object::FLOAT(a), b) if is_number(&b) => {
+            eval_float_infix_expression(operator, a, to_float(b))
+        }
+        (a, Object::FLOAT(b)) if is_number(&a) => {
+            eval_float_infix_expression(operator, to_float(a), b)
+        }
+        (s, t) if s.get_type() != t.get_type() => Object::error(
+            ErrorKind::TypeMismatch,
+            format!(
+                "type mismatch: {} {} {}",
+                s.get_type(),
+                operator,
+                t.get_type()
+            ),
+        ),
+        (left, right) => Object::error(
+            ErrorKind::UnknownOperator,
+            format!(
+                "unknown operator: {} {} {}",
+                left.get_type(),
+                operator,
+                right.get_type()
+            ),
+        ),
+    }
+}
+
+fn eval_range_expression(start: Object, end: Object) -> Object {
+    match (start, end) {
+        (Object::INTEGER(a), Object::INTEGER(b)) => Object::RANGE(a, b),
+        (
+            a @ (Object::INTEGER(_) | Object::BIGINT(_)),
+            b @ (Object::INTEGER(_) | Object::BIGINT(_)),
+        ) => Object::error(
+            ErrorKind::IntegerOverflow,
+            format!("range bound too large: {a}..{b}"),
+        ),
+        (a, b) => Object::error(
+            ErrorKind::TypeMismatch,
+            format!(
+                "range bounds must be INTEGER: {}..{}",
+                a.get_type(),
+                b.get_type()
+            ),
+        ),
+    }
+}
+
+fn eval_boolean_infix_expression(operator: &Token, a: bool, b: bool) -> Object {
+    match operator {
+        Token::EQ => Object::BOOLEAN(a == b),
+        Token::NE => Object::BOOLEAN(a != b),
+        _ => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: BOOLEAN {operator} BOOLEAN"),
+        ),
+    }
+}
+
+/// `/` and `%` truncate towards zero, so `a % b` has the sign of `a`.
+/// `>>` is an arithmetic shift.
+fn eval_integer_infix_expression(operator: &Token, a: i32, b: i32) -> Object {
+    match operator {
+        Token::DIV | Token::MOD if b == 0 => Object::error(
+            ErrorKind::DivisionByZero,
+            format!("division by zero: {a} {operator} {b}"),
+        ),
+        Token::POW if b < 0 => Object::error(
+            ErrorKind::InvalidOperand,
+            format!("negative exponent: {a} {operator} {b}"),
+        ),
+        Token::SHL | Token::SHR if b < 0 => Object::error(
+            ErrorKind::InvalidOperand,
+            format!("negative shift count: {a} {operator} {b}"),
+        ),
+        Token::PLUS => checked_integer(a.checked_add(b), a, operator, b),
+        Token::SUB => checked_integer(a.checked_sub(b), a, operator, b),
+        Token::MUL => checked_integer(a.checked_mul(b), a, operator, b),
+        Token::DIV => checked_integer(a.checked_div(b), a, operator, b),
+        Token::MOD => checked_integer(a.checked_rem(b), a, operator, b),
+        Token::POW => checked_integer(a.checked_pow(b as u32), a, operator, b),
+        Token::SHL => {
+            let shifted = (b < 32).then(|| i32::try_from((a as i64) << b).ok());
+            checked_integer(shifted.flatten(), a, operator, b)
+        }
+        Token::SHR => Object::INTEGER(a >> b.min(31)),
+        Token::BITAND => Object::INTEGER(a & b),
+        Token::BITOR => Object::INTEGER(a | b),
+        Token::BITXOR => Object::INTEGER(a ^ b),
+        Token::EQ => native_bool_to_object(a == b),
+        Token::NE => native_bool_to_object(a != b),
+        Token::GT => native_bool_to_object(a > b),
+        Token::LT => native_bool_to_object(a < b),
+        Token::GE => native_bool_to_object(a >= b),
+        Token::LE => native_bool_to_object(a <= b),
+        _ => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: INTEGER {} INTEGER", operator),
+        ),
+    }
+}
+
+/// Result of an `i32` operation, promoted to a `BIGINT` when it overflowed.
+fn checked_integer(result: Option<i32>, a: i32, operator: &Token, b: i32) -> Object {
+    result.map_or_else(
+        || eval_big_integer_infix_expression(operator, BigInt::from(a), BigInt::from(b)),
+        Object::INTEGER,
+    )
+}
+
+fn eval_big_integer_infix_expression(operator: &Token, a: BigInt, b: BigInt) -> Object {
+    match operator {
+        Token::DIV | Token::MOD if b.is_zero() => Object::error(
+            ErrorKind::DivisionByZero,
+            format!("division by zero: {a} {operator} {b}"),
+        ),
+        Token::POW if b.is_negative() => Object::error(
+            ErrorKind::InvalidOperand,
+            format!("negative exponent: {a} {operator} {b}"),
+        ),
+        Token::SHL | Token::SHR if b.is_negative() => Object::error(
+            ErrorKind::InvalidOperand,
+            format!("negative shift count: {a} {operator} {b}"),
+        ),
+        Token::PLUS => Object::integer(&a + &b),
+        Token::SUB => Object::integer(&a - &b),
+        Token::MUL if a.bits() + b.bits() > MAX_INTEGER_BITS => integer_too_large(operator, a, b),
+        Token::MUL => Object::integer(&a * &b),
+        Token::DIV => Object::integer(&a / &b),
+        Token::MOD => Object::integer(&a % &b),
+        Token::POW => match b.to_i32() {
+            Some(exponent) if a.bits().saturating_mul(exponent as usize) <= MAX_INTEGER_BITS => {
+                Object::integer(a.pow(exponent as u32))
+            }
+            _ => integer_too_large(operator, a, b),
+        },
+        Token::SHL if a.is_zero() => Object::INTEGER(0),
+        Token::SHL => match b.to_i32() {
+            Some(shift) if a.bits().saturating_add(shift as usize) <= MAX_INTEGER_BITS => {
+                Object::integer(&a << shift as usize)
+            }
+            _ => integer_too_large(operator, a, b),
+        },
+        Token::SHR => Object::integer(&a >> b.to_i32().map_or(usize::MAX, |b| b as usize)),
+        Token::BITAND => Object::integer(&a & &b),
+        Token::BITOR => Object::integer(&a | &b),
+        Token::BITXOR => Object::integer(&a ^ &b),
+        Token::EQ => native_bool_to_object(a == b),
+        Token::NE => native_bool_to_object(a != b),
+        Token::GT => native_bool_to_object(a > b),
+        Token::LT => native_bool_to_object(a < b),
+        Token::GE => native_bool_to_object(a >= b),
+        Token::LE => native_bool_to_object(a <= b),
+        _ => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: INTEGER {} INTEGER", operator),
+        ),
+    }
+}
+
+fn integer_too_large(operator: &Token, a: BigInt, b: BigInt) -> Object {
+    // Printing the operands of a large product would take longer than computing it.
+    let show = |value: BigInt| match value.bits() {
+        bits if bits > 64 => format!("<{bits}-bit integer>"),
+        _ => value.to_string(),
+    };
+    Object::error(
+        ErrorKind::IntegerOverflow,
+        format!("integer too large: {} {operator} {}", show(a), show(b)),
+    )
+}
+
+fn eval_float_infix_expression(operator: &Token, a: f64, b: f64) -> Object {
+    match operator {
+        Token::DIV | Token::MOD if b == 0.0 => Object::error(
+            ErrorKind::DivisionByZero,
+            format!("division by zero: {a:?} {operator} {b:?}"),
+        ),
+        Token::PLUS => float_result(operator, a, b, a + b),
+        Token::SUB => float_result(operator, a, b, a - b),
+        Token::MUL => float_result(operator, a, b, a * b),
+        Token::DIV => float_result(operator, a, b, a / b),
+        Token::MOD => float_result(operator, a, b, a % b),
+        Token::POW => float_result(operator, a, b, a.powf(b)),
+        Token::EQ => native_bool_to_object(a == b),
+        Token::NE => native_bool_to_object(a != b),
+        Token::GT => native_bool_to_object(a > b),
+        Token::LT => native_bool_to_object(a < b),
+        Token::GE => native_bool_to_object(a >= b),
+        Token::LE => native_bool_to_object(a <= b),
+        _ => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: FLOAT {} FLOAT", operator),
+        ),
+    }
+}
+
+/// Infinities and NaN have no literal, so they are errors instead of values.
+fn float_result(operator: &Token, a: f64, b: f64, result: f64) -> Object {
+    if result.is_nan() {
+        Object::error(
+            ErrorKind::InvalidOperand,
+            format!("not a number: {a:?} {operator} {b:?}"),
+        )
+    } else if result.is_infinite() {
+        Object::error(
+            ErrorKind::FloatOverflow,
+            format!("float too large: {a:?} {operator} {b:?}"),
+        )
+    } else {
+        Object::FLOAT(result)
+    }
+}
+
+fn is_number(object: &Object) -> bool {
+    matches!(
+        object,
+        Object::INTEGER(_) | Object::BIGINT(_) | Object::FLOAT(_)
+    )
+}
+
+fn to_float(object: Object) -> f64 {
+    match object {
+        Object::INTEGER(i) => i as f64,
+        Object::BIGINT(b) => b.to_f64(),
+        Object::FLOAT(f) => f,
+        obj => unreachable!("{} is not a number", obj.get_type()),
+    }
+}
+
+fn to_big_integer(object: Object) -> BigInt {
+    match object {
+        Object::INTEGER(i) => BigInt::from(i),
+        Object::BIGINT(b) => b,
+        obj => unreachable!("{} is not an integer", obj.get_type()),
+    }
+}
+
+fn eval_string_infix_expression(operator: &Token, a: String, b: String) -> Object {
+    match operator {
+        Token::PLUS => Object::STRING(a + &b),
+        _ => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: STRING {} STRING", operator),
+        ),
+    }
+}
+
+fn eval_bang_expression(object: Object) -> Object {
+    native_bool_to_object(!is_truthy(&object))
+}
+
+fn eval_minus_expression(object: Object) -> Object {
+    match object {
+        Object::INTEGER(i) => i
+            .checked_neg()
+            .map_or_else(|| Object::integer(-BigInt::from(i)), Object::INTEGER),
+        Object::BIGINT(b) => Object::integer(-b),
+        Object::FLOAT(f) => Object::FLOAT(-f),
+        obj => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: -{}", obj.get_type()),
+        ),
+    }
+}
+
+fn eval_bitwise_not_expression(object: Object) -> Object {
+    match object {
+        Object::INTEGER(i) => Object::INTEGER(!i),
+        Object::BIGINT(b) => Object::integer(!&b),
+        obj => Object::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: ~{}", obj.get_type()),
+        ),
+    }
+}
+
+pub fn eval_index_expression(left: Object, index: Object) -> Object {
+    match (left, index) {
+        (Object::ARRAY(a), Object::INTEGER(i)) => eval_array_index_expression(&a.borrow(), i),
+        (Object::ARRAY(_), Object::BIGINT(_)) => NULL,
+        (Object::HASH(h), key) => match key.hash_key() {
+            Some(hash_key) => h
+                .borrow()
+                .get(&hash_key)
+                .map_or(NULL, |pair| pair.value.clone()),
+            None => Object::error(
+                ErrorKind::UnusableHashKey,
+                format!("unusable as hash key: {}", key.get_type()),
+            ),
+        },
+        (obj, _) => Object::error(
+            ErrorKind::UnsupportedIndex,
+            format!("index operator not supported for {obj}."),
+        ),
+    }
+}
+
+fn eval_array_index_expression(left: &Vector<Object>, index: i32) -> Object {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| left.get(index))
+        .map_or(NULL, |element| element.clone())
+}
+
+pub fn eval_hash_literal(pairs: Vec<(Object, Object)>) -> Object {
+    let mut hash = BTreeMap::new();
+
+    for (key, value) in pairs {
+        match key.hash_key() {
+            Some(hash_key) => {
+                hash.insert(hash_key, HashPair { key, value });
+            }
+            None => {
+                return Object::error(
+                    ErrorKind::UnusableHashKey,
+                    format!("unusable as hash key: {}", key.get_type()),
+                )
+            }
+        }
+    }
+
+    Object::hash(hash)
+}
+
+/// Number of entries of `object` when it is a hash, which index assignments can grow.
+pub fn hash_len(object: &Object) -> usize {
+    match object {
+        Object::HASH(hash) => hash.borrow().len(),
+        _ => 0,
+    }
+}
+
+/// Stores `value` at `index` in the collection, returning `value`. Arrays can only replace
+/// existing elements, hashes insert the key when it is missing.
+pub fn eval_index_assignment(left: Object, index: Object, value: Object) -> Object {
+    match (left, index) {
+        (Object::ARRAY(a), Object::INTEGER(i)) => {
+            let mut elements = a.borrow_mut();
+            let len = elements.len();
+            match usize::try_from(i)
+                .ok()
+                .and_then(|index| elements.get_mut(index))
+            {
+                Some(element) => {
+                    *element = value.clone();
+                    value
+                }
+                None => index_out_of_bounds(i, len),
+            }
+        }
+        (Object::ARRAY(a), Object::BIGINT(i)) => index_out_of_bounds(i, a.borrow().len()),
+        (Object::HASH(h), key) => match key.hash_key() {
+            Some(hash_key) => {
+                h.borrow_mut().insert(
+                    hash_key,
+                    HashPair {
+                        key,
+                        value: value.clone(),
+                    },
+                );
+                value
+            }
+            None => Object::error(
+                ErrorKind::UnusableHashKey,
+                format!("unusable as hash key: {}", key.get_type()),
+            ),
+        },
+        (obj, _) => Object::error(
+            ErrorKind::UnsupportedIndex,
+            format!("index assignment not supported for {}", obj.get_type()),
+        ),
+    }
+}
+
+fn index_out_of_bounds(index: impl fmt::Display, len: usize) -> Object {
+    Object::error(
+        ErrorKind::IndexOutOfBounds,
+        format!("index out of bounds: {index} (length {len})"),
+    )
+}
+
+pub fn native_bool_to_object(b: bool) -> Object {
+    if b {
+        TRUE
+    } else {
+        FALSE
+    }
+}
@@ -0,0 +1,204 @@
+//! Persistent vector backing `Object::ARRAY`: clones share their structure, and updating
+//! one only copies the path from the root to the changed element.
+
+use std::{fmt, iter::FromIterator, rc::Rc};
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+/// Node of the trie, visited by the cycle collector.
+#[derive(Clone)]
+pub(crate) enum Node<T> {
+    Branch(Vec<Rc<Node<T>>>),
+    Leaf(Vec<T>),
+}
+
+/// Elements `start..end` of a trie with 32 children per node.
+///
+/// Cloning is O(1), `get`, `get_mut`, `push` and `tail` are O(log n). Nodes are only
+/// copied when they are shared, so updating a vector nobody else holds happens in place.
+#[derive(Clone)]
+pub struct Vector<T> {
+    root: Rc<Node<T>>,
+    /// Index bits consumed above the leaves, zero when the root is a leaf.
+    shift: usize,
+    /// `tail` drops elements by moving `start`, the trie keeps them.
+    start: usize,
+    end: usize,
+}
+
+impl<T: Clone> Vector<T> {
+    pub fn new() -> Self {
+        Vector {
+            root: Rc::new(Node::Leaf(Vec::new())),
+            shift: 0,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    pub(crate) fn root(&self) -> &Rc<Node<T>> {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let index = self.start + index;
+        let mut node = self.root.as_ref();
+        let mut shift = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    node = &children[(index >> shift) & MASK];
+                    shift -= BITS;
+                }
+                Node::Leaf(values) => return values.get(index & MASK),
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None;
+        }
+        let index = self.start + index;
+        let mut node = Rc::make_mut(&mut self.root);
+        let mut shift = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    node = Rc::make_mut(&mut children[(index >> shift) & MASK]);
+                    shift -= BITS;
+                }
+                Node::Leaf(values) => return values.get_mut(index & MASK),
+            }
+        }
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn push(&mut self, value: T) {
+        let index = self.end;
+        if index == WIDTH << self.shift {
+            // The trie is full, the current root becomes the first child of a new level.
+            self.root = Rc::new(Node::Branch(vec![Rc::clone(&self.root)]));
+            self.shift += BITS;
+        }
+
+        let mut node = Rc::make_mut(&mut self.root);
+        let mut shift = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    let slot = (index >> shift) & MASK;
+                    if slot == children.len() {
+                        children.push(Rc::new(if shift == BITS {
+                            Node::Leaf(Vec::with_capacity(WIDTH))
+                        } else {
+                            Node::Branch(Vec::new())
+                        }));
+                    }
+                    node = Rc::make_mut(&mut children[slot]);
+                    shift -= BITS;
+                }
+                Node::Leaf(values) => {
+                    values.push(value);
+                    break;
+                }
+            }
+        }
+        self.end += 1;
+    }
+
+    /// Every element but the first one, `None` when the vector is empty.
+    pub fn tail(&self) -> Option<Self> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(Vector {
+            start: self.start + 1,
+            ..self.clone()
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+}
+
+impl<T: Clone> Default for Vector<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> FromIterator<T> for Vector<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vector = Vector::new();
+        iter.into_iter().for_each(|value| vector.push(value));
+        vector
+    }
+}
+
+impl<T: Clone> From<Vec<T>> for Vector<T> {
+    fn from(values: Vec<T>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+/// Owning iterator, holding a clone of the vector so later updates do not affect it.
+pub struct IntoIter<T> {
+    vector: Vector<T>,
+    index: usize,
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let value = self.vector.get(self.index).cloned();
+        self.index += 1;
+        value
+    }
+}
+
+impl<T: Clone> IntoIterator for Vector<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            vector: self,
+            index: 0,
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for Vector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for Vector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
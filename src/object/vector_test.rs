@@ -0,0 +1,68 @@
+#[cfg(test)]
+pub mod vector_test {
+    use crate::object::vector::Vector;
+
+    #[test]
+    fn push_and_get() {
+        // Crosses the boundaries where the trie grows a second and a third level.
+        let mut vector = Vector::new();
+        (0..2000).for_each(|i| vector.push(i));
+
+        assert_eq!(vector.len(), 2000);
+        (0..2000).for_each(|i| assert_eq!(vector.get(i), Some(&i)));
+        assert_eq!(vector.get(2000), None);
+        assert_eq!(vector.first(), Some(&0));
+        assert_eq!(vector.last(), Some(&1999));
+        assert_eq!(
+            vector.iter().copied().collect::<Vec<usize>>(),
+            (0..2000).collect::<Vec<usize>>()
+        );
+
+        let empty: Vector<usize> = Vector::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.first(), None);
+        assert_eq!(empty.last(), None);
+    }
+
+    #[test]
+    fn clones_are_independent() {
+        let original: Vector<usize> = (0..1100).collect();
+        let mut copy = original.clone();
+        copy.push(1100);
+        *copy.get_mut(3).unwrap() = 42;
+        *copy.get_mut(1050).unwrap() = 43;
+
+        assert_eq!(original.len(), 1100);
+        assert_eq!(original.get(3), Some(&3));
+        assert_eq!(original.get(1050), Some(&1050));
+        assert_eq!(copy.len(), 1101);
+        assert_eq!(copy.get(3), Some(&42));
+        assert_eq!(copy.get(1050), Some(&43));
+        assert_eq!(copy.last(), Some(&1100));
+        assert_eq!(copy.get_mut(1101), None);
+    }
+
+    #[test]
+    fn tail() {
+        let vector: Vector<usize> = (0..40).collect();
+        let mut tail = vector.tail().unwrap();
+        assert_eq!(tail.len(), 39);
+        assert_eq!(tail.first(), Some(&1));
+
+        tail.push(40);
+        *tail.get_mut(0).unwrap() = 100;
+        assert_eq!(
+            tail.iter().copied().collect::<Vec<usize>>()[..3],
+            [100, 2, 3]
+        );
+        assert_eq!(tail.last(), Some(&40));
+        assert_eq!(vector, (0..40).collect());
+
+        let mut rest = Vector::from(vec![1]);
+        rest = rest.tail().unwrap();
+        assert!(rest.is_empty());
+        assert!(rest.tail().is_none());
+        rest.push(2);
+        assert_eq!(rest, Vector::from(vec![2]));
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use crate::token::token::{Span, Token};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    ExpectedToken {
+        expected: Token,
+        found: Token,
+        span: Span,
+    },
+    NoPrefixRule {
+        token: Token,
+        span: Span,
+    },
+    NoInfixRule {
+        token: Token,
+        span: Span,
+    },
+    InvalidInteger {
+        literal: String,
+        span: Span,
+    },
+    InvalidFloat {
+        literal: String,
+        span: Span,
+    },
+    IllegalToken {
+        literal: String,
+        span: Span,
+    },
+    UnterminatedBlock {
+        span: Span,
+    },
+    UnterminatedString {
+        span: Span,
+    },
+    InvalidEscape {
+        sequence: String,
+        span: Span,
+    },
+    UnterminatedComment {
+        span: Span,
+    },
+    InvalidAssignmentTarget {
+        target: String,
+        span: Span,
+    },
+    /// `break` or `continue` outside of a loop body.
+    OutsideLoop {
+        keyword: Token,
+        span: Span,
+    },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::ExpectedToken { span, .. } => *span,
+            ParseError::NoPrefixRule { span, .. } => *span,
+            ParseError::NoInfixRule { span, .. } => *span,
+            ParseError::InvalidInteger { span, .. } => *span,
+            ParseError::InvalidFloat { span, .. } => *span,
+            ParseError::IllegalToken { span, .. } => *span,
+            ParseError::UnterminatedBlock { span } => *span,
+            ParseError::UnterminatedString { span } => *span,
+            ParseError::InvalidEscape { span, .. } => *span,
+            ParseError::UnterminatedComment { span } => *span,
+            ParseError::InvalidAssignmentTarget { span, .. } => *span,
+            ParseError::OutsideLoop { span, .. } => *span,
+        }
+    }
+
+    fn describe(token: &Token) -> String {
+        match token {
+            Token::IDENT(s) if s.is_empty() => "identifier".to_string(),
+            Token::INT(s) if s.is_empty() => "integer".to_string(),
+            Token::FLOAT(s) if s.is_empty() => "float".to_string(),
+            Token::STRING(s) if s.is_empty() => "string".to_string(),
+            Token::STRING(s) => format!("\"{s}\""),
+            t => t.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::ExpectedToken {
+                expected, found, ..
+            } => write!(
+                f,
+                "expected {}, found {}",
+                Self::describe(expected),
+                Self::describe(found)
+            ),
+            ParseError::NoPrefixRule { token, .. } => write!(
+                f,
+                "no prefix parse function found for {}",
+                Self::describe(token)
+            ),
+            ParseError::NoInfixRule { token, .. } => write!(
+                f,
+                "no infix parse function found for {}",
+                Self::describe(token)
+            ),
+            ParseError::InvalidInteger { literal, .. } => {
+                write!(f, "invalid integer literal: {literal}")
+            }
+            ParseError::InvalidFloat { literal, .. } => {
+                write!(f, "invalid float literal: {literal}")
+            }
+            ParseError::IllegalToken { literal, .. } => write!(f, "illegal token: {literal}"),
+            ParseError::UnterminatedBlock { .. } => write!(f, "unterminated block, expected }}"),
+            ParseError::UnterminatedString { .. } => {
+                write!(f, "unterminated string, expected \"")
+            }
+            ParseError::InvalidEscape { sequence, .. } => {
+                write!(f, "invalid escape sequence: {sequence}")
+            }
+            ParseError::UnterminatedComment { .. } => {
+                write!(f, "unterminated comment, expected */")
+            }
+            ParseError::InvalidAssignmentTarget { target, .. } => {
+                write!(f, "invalid assignment target: {target}")
+            }
+            ParseError::OutsideLoop { keyword, .. } => {
+                write!(
+                    f,
+                    "{} outside of a loop",
+                    keyword.to_string().to_lowercase()
+                )
+            }
+        }
+    }
+}
@@ -1,41 +1,74 @@
 use crate::{
     ast::ast::{
-        Arrays, CallExpression, Expression, FnExpression, Identifier, IfExpression, Indexed,
-        InfixExpr, LetStatement, PrefixExpr, Program, ReturnStatement, Statement,
+        Arrays, AssignExpression, CallExpression, Expression, FnExpression, ForStatement,
+        HashLiteral, Identifier, IfExpression, Indexed, InfixExpr, LetStatement, PrefixExpr,
+        Program, ReturnStatement, Statement, WhileStatement,
     },
     lexer::lexer::Lexer,
-    token::token::Token,
+    object::bigint::BigInt,
+    parser::error::ParseError,
+    token::token::{Span, SpannedToken, Token},
 };
 
 pub struct Parser {
     lexer: Lexer,
     current_tok: Token,
+    current_span: Span,
     peek_tok: Token,
-    errors: Vec<String>,
+    peek_span: Span,
+    errors: Vec<ParseError>,
+    /// Number of loops enclosing the current token within the current function.
+    loop_depth: usize,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Precedence {
     LOWEST = 1,
-    EQUALS = 2,
-    LESSGREATER = 3,
-    SUM = 4,
-    PRODUCT = 5,
-    PREFIX = 6,
-    CALL = 7,
-    INDEX = 8,
+    ASSIGN = 2,
+    OR = 3,
+    AND = 4,
+    EQUALS = 5,
+    LESSGREATER = 6,
+    RANGE = 7,
+    BITOR = 8,
+    BITXOR = 9,
+    BITAND = 10,
+    SHIFT = 11,
+    SUM = 12,
+    PRODUCT = 13,
+    PREFIX = 14,
+    POWER = 15,
+    CALL = 16,
+    INDEX = 17,
 }
 
 fn token_to_precedence(token: Token) -> Precedence {
     match token {
+        Token::ASSIGN => Precedence::ASSIGN,
+        Token::PLUSASSIGN => Precedence::ASSIGN,
+        Token::SUBASSIGN => Precedence::ASSIGN,
+        Token::MULASSIGN => Precedence::ASSIGN,
+        Token::DIVASSIGN => Precedence::ASSIGN,
+        Token::OR => Precedence::OR,
+        Token::AND => Precedence::AND,
         Token::EQ => Precedence::EQUALS,
         Token::NE => Precedence::EQUALS,
         Token::LT => Precedence::LESSGREATER,
         Token::GT => Precedence::LESSGREATER,
+        Token::LE => Precedence::LESSGREATER,
+        Token::GE => Precedence::LESSGREATER,
+        Token::DOTDOT => Precedence::RANGE,
+        Token::BITOR => Precedence::BITOR,
+        Token::BITXOR => Precedence::BITXOR,
+        Token::BITAND => Precedence::BITAND,
+        Token::SHL => Precedence::SHIFT,
+        Token::SHR => Precedence::SHIFT,
         Token::PLUS => Precedence::SUM,
         Token::SUB => Precedence::SUM,
         Token::MUL => Precedence::PRODUCT,
         Token::DIV => Precedence::PRODUCT,
+        Token::MOD => Precedence::PRODUCT,
+        Token::POW => Precedence::POWER,
         Token::LPAREN => Precedence::CALL,
         Token::LBRACKET => Precedence::INDEX,
         _ => Precedence::LOWEST,
@@ -44,21 +77,42 @@ fn token_to_precedence(token: Token) -> Precedence {
 
 impl Parser {
     pub fn new(lexer: Lexer) -> Self {
-        Parser {
+        let mut parser = Parser {
             lexer,
-            current_tok: Token::ILLEGAL(String::new()),
-            peek_tok: Token::ILLEGAL(String::new()),
+            current_tok: Token::EOF,
+            current_span: Span::default(),
+            peek_tok: Token::EOF,
+            peek_span: Span::default(),
             errors: Vec::new(),
-        }
+            loop_depth: 0,
+        };
+        parser.next_token();
+        parser.next_token();
+        parser
     }
 
-    pub fn errors(&self) -> Vec<String> {
-        self.errors.clone()
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
     }
 
     pub fn next_token(&mut self) {
-        self.current_tok = self.peek_tok.clone();
-        self.peek_tok = self.lexer.next_token();
+        let SpannedToken { token, span } = loop {
+            // Comment trivia is only meaningful to tooling, skip it when the lexer emits it.
+            match self.lexer.next_spanned_token() {
+                SpannedToken {
+                    token: Token::COMMENT(_),
+                    ..
+                } => continue,
+                spanned => break spanned,
+            }
+        };
+        self.current_tok = std::mem::replace(&mut self.peek_tok, token);
+        self.current_span = std::mem::replace(&mut self.peek_span, span);
+    }
+
+    /// Span going from `start` up to the end of the current token.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.current_span)
     }
 
     pub fn parse_program(&mut self) -> Program {
@@ -67,7 +121,7 @@ impl Parser {
         while !self.current_token_is(&Token::EOF) {
             match self.parse_statement() {
                 Some(statement) => program.push(statement),
-                None => (),
+                None => self.synchronize(),
             }
             self.next_token();
         }
@@ -75,20 +129,47 @@ impl Parser {
         program
     }
 
+    /// Panic-mode recovery: skips the rest of a broken statement, up to its `;` or
+    /// right before the `}` closing the enclosing block, so parsing resumes cleanly.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+
+        loop {
+            match self.current_tok {
+                Token::EOF => return,
+                Token::SEMICOLON if depth == 0 => return,
+                Token::LBRACE => depth += 1,
+                Token::RBRACE if depth > 0 => depth -= 1,
+                _ => (),
+            }
+
+            if depth == 0 && self.peek_token_is(&Token::RBRACE) {
+                return;
+            }
+            self.next_token();
+        }
+    }
+
     pub fn parse_statement(&mut self) -> Option<Statement> {
         match self.current_tok {
             Token::LET => self.parse_let_statement(),
             Token::RETURN => self.parse_return_statement(),
+            Token::WHILE => self.parse_while_statement(),
+            Token::FOR => self.parse_for_statement(),
+            Token::BREAK => self.parse_loop_control_statement(),
+            Token::CONTINUE => self.parse_loop_control_statement(),
             _ => self.parse_expression_statement(),
         }
     }
 
     pub fn parse_let_statement(&mut self) -> Option<Statement> {
+        let start = self.current_span;
+
         if !self.expect_token(&Token::IDENT(String::new())) {
             return None;
         };
 
-        let name: Identifier = Identifier::new(self.current_tok.to_string());
+        let name: Identifier = Identifier::new(self.current_tok.to_string(), self.current_span);
 
         if !self.expect_token(&Token::ASSIGN) {
             return None;
@@ -96,37 +177,130 @@ impl Parser {
 
         self.next_token();
 
-        let value = match self.parse_expression(Precedence::LOWEST) {
-            None => Expression::Identifier(Identifier {
-                value: "ILLEGAL".to_string(),
-            }),
-            Some(expr) => expr,
-        };
+        let value = self.parse_expression(Precedence::LOWEST)?;
+        let span = self.span_from(start);
 
-        //TODO!: "We're skipping the expressions until we encounter a semicolon
-        while !self.current_token_is(&Token::SEMICOLON) && !self.current_token_is(&Token::EOF) {
+        if self.peek_token_is(&Token::SEMICOLON) {
             self.next_token();
         }
 
-        Some(Statement::LetStatement(LetStatement::new(name, value)))
+        Some(Statement::LetStatement(LetStatement::new(
+            name, value, span,
+        )))
     }
 
     pub fn parse_return_statement(&mut self) -> Option<Statement> {
+        let start = self.current_span;
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::LOWEST)?;
+        let span = self.span_from(start);
+
+        if self.peek_token_is(&Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::ReturnStatement(ReturnStatement::new(
+            value, span,
+        )))
+    }
+
+    pub fn parse_while_statement(&mut self) -> Option<Statement> {
+        let start = self.current_span;
+
+        if !self.expect_token(&Token::LPAREN) {
+            return None;
+        };
+
+        // `break` and `continue` only apply to the body, not even to an enclosing loop from
+        // the condition.
         self.next_token();
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let condition = self.parse_expression(Precedence::LOWEST);
+        self.loop_depth = loop_depth;
+        let condition = condition?;
+
+        if !self.expect_token(&Token::RPAREN) {
+            return None;
+        };
 
-        let value = match self.parse_expression(Precedence::LOWEST) {
-            None => Expression::Identifier(Identifier {
-                value: "ILLEGAL".to_string(),
-            }),
-            Some(expr) => expr,
+        if !self.expect_token(&Token::LBRACE) {
+            return None;
         };
 
-        //TODO!: "We're skipping the expressions until we encounter a semicolon
-        while !self.current_token_is(&Token::SEMICOLON) && !self.current_token_is(&Token::EOF) {
+        self.loop_depth += 1;
+        let body = self.parse_block_statements();
+        self.loop_depth -= 1;
+        let span = self.span_from(start);
+
+        if self.peek_token_is(&Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::WhileStatement(WhileStatement::new(
+            condition, body?, span,
+        )))
+    }
+
+    pub fn parse_for_statement(&mut self) -> Option<Statement> {
+        let start = self.current_span;
+
+        if !self.expect_token(&Token::LPAREN) {
+            return None;
+        };
+
+        if !self.expect_token(&Token::IDENT(String::new())) {
+            return None;
+        }
+        let variable = Identifier::new(self.current_tok.to_string(), self.current_span);
+
+        if !self.expect_token(&Token::IN) {
+            return None;
+        };
+
+        // The iterable is evaluated once, before the loop starts.
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::LOWEST)?;
+
+        if !self.expect_token(&Token::RPAREN) {
+            return None;
+        };
+
+        if !self.expect_token(&Token::LBRACE) {
+            return None;
+        };
+
+        self.loop_depth += 1;
+        let body = self.parse_block_statements();
+        self.loop_depth -= 1;
+        let span = self.span_from(start);
+
+        if self.peek_token_is(&Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(Statement::ForStatement(ForStatement::new(
+            variable, iterable, body?, span,
+        )))
+    }
+
+    pub fn parse_loop_control_statement(&mut self) -> Option<Statement> {
+        let keyword = self.current_tok.clone();
+        let span = self.current_span;
+
+        if self.loop_depth == 0 {
+            self.peek_errors(ParseError::OutsideLoop { keyword, span });
+            return None;
+        }
+
+        if self.peek_token_is(&Token::SEMICOLON) {
             self.next_token();
         }
 
-        Some(Statement::ReturnStatement(ReturnStatement::new(value)))
+        match keyword {
+            Token::BREAK => Some(Statement::BreakStatement(span)),
+            _ => Some(Statement::ContinueStatement(span)),
+        }
     }
 
     pub fn parse_expression_statement(&mut self) -> Option<Statement> {
@@ -136,84 +310,135 @@ impl Parser {
             self.next_token();
         }
 
-        match expr_statement {
-            None => None,
-            Some(expr) => Some(Statement::ExpressionStatement(expr)),
-        }
+        expr_statement.map(Statement::ExpressionStatement)
     }
 
     pub fn parse_expression(&mut self, prec: Precedence) -> Option<Expression> {
-        match self.parse_prefix(&self.current_tok.clone()) {
-            None => None,
-            Some(left_expr) => {
-                let mut expr = left_expr;
-                while !self.peek_token_is(&Token::SEMICOLON) && prec < self.peek_precedence() {
-                    self.next_token();
-                    match self.parse_infix(&self.current_tok.clone(), expr.clone()) {
-                        Some(infix_expr) => {
-                            expr = infix_expr.clone();
-                        }
-                        None => {
-                            return Some(expr.clone());
-                        }
-                    }
-                }
-                Some(expr.clone())
-            }
+        let mut expr = self.parse_prefix(&self.current_tok.clone())?;
+
+        while !self.peek_token_is(&Token::SEMICOLON) && prec < self.peek_precedence() {
+            self.next_token();
+            expr = self.parse_infix(&self.current_tok.clone(), expr)?;
         }
+
+        Some(expr)
     }
 
     pub fn parse_identifier(&mut self) -> Option<Expression> {
         Some(Expression::new(Identifier::new(
             self.current_tok.to_string(),
+            self.current_span,
         )))
     }
 
     pub fn parse_integer(&mut self) -> Option<Expression> {
         match &self.current_tok {
-            Token::INT(i) => Some(Expression::Integer(i.clone().parse::<i32>().unwrap())),
+            Token::INT(i) => match (i.parse::<i32>(), BigInt::parse(i)) {
+                (Ok(int), _) => Some(Expression::Integer(int, self.current_span)),
+                (Err(_), Some(int)) => Some(Expression::BigInteger(int, self.current_span)),
+                (Err(_), None) => {
+                    self.peek_errors(ParseError::InvalidInteger {
+                        literal: i.clone(),
+                        span: self.current_span,
+                    });
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    pub fn parse_float(&mut self) -> Option<Expression> {
+        match &self.current_tok {
+            Token::FLOAT(f) => match f.parse::<f64>() {
+                Ok(float) if float.is_finite() => Some(Expression::Float(float, self.current_span)),
+                _ => {
+                    self.peek_errors(ParseError::InvalidFloat {
+                        literal: f.clone(),
+                        span: self.current_span,
+                    });
+                    None
+                }
+            },
             _ => None,
         }
     }
 
     pub fn parse_string(&mut self) -> Option<Expression> {
         match &self.current_tok {
-            Token::STRING(s) => Some(Expression::String(s.to_string())),
+            Token::STRING(s) => Some(Expression::String(s.to_string(), self.current_span)),
             _ => None,
         }
     }
 
     pub fn parse_boolean(&mut self) -> Option<Expression> {
-        Some(Expression::Bool(self.current_token_is(&Token::TRUE)))
+        Some(Expression::Bool(
+            self.current_token_is(&Token::TRUE),
+            self.current_span,
+        ))
     }
 
     pub fn parse_prefix_expression(&mut self) -> Option<Expression> {
+        let start = self.current_span;
         let prefix_token = self.current_tok.clone();
         self.next_token();
 
-        if let Some(expr) = self.parse_expression(Precedence::PREFIX) {
-            Some(Expression::Prefix(PrefixExpr::new(prefix_token, expr)))
-        } else {
-            None
-        }
+        let expr = self.parse_expression(Precedence::PREFIX)?;
+        let span = self.span_from(start);
+        Some(Expression::Prefix(PrefixExpr::new(
+            prefix_token,
+            expr,
+            span,
+        )))
     }
 
     pub fn parse_prefix(&mut self, token: &Token) -> Option<Expression> {
-        match &token {
+        match token {
             Token::IDENT(_) => self.parse_identifier(),
             Token::INT(_) => self.parse_integer(),
+            Token::FLOAT(_) => self.parse_float(),
             Token::STRING(_) => self.parse_string(),
             Token::BANG => self.parse_prefix_expression(),
             Token::SUB => self.parse_prefix_expression(),
+            Token::TILDE => self.parse_prefix_expression(),
             Token::TRUE => self.parse_boolean(),
             Token::FALSE => self.parse_boolean(),
             Token::LPAREN => self.parse_grouped_expression(),
             Token::IF => self.parse_if_expression(),
             Token::FUNCTION => self.parse_function_expression(),
             Token::LBRACKET => self.parse_arrays(),
-            Token::ILLEGAL(_) => None,
+            Token::LBRACE => self.parse_hash_literal(),
+            Token::UNTERMINATED => {
+                self.peek_errors(ParseError::UnterminatedString {
+                    span: self.current_span,
+                });
+                None
+            }
+            Token::OPENCOMMENT => {
+                self.peek_errors(ParseError::UnterminatedComment {
+                    span: self.current_span,
+                });
+                None
+            }
+            Token::BADESCAPE(sequence) => {
+                self.peek_errors(ParseError::InvalidEscape {
+                    sequence: sequence.clone(),
+                    span: self.current_span,
+                });
+                None
+            }
+            Token::ILLEGAL(literal) => {
+                self.peek_errors(ParseError::IllegalToken {
+                    literal: literal.clone(),
+                    span: self.current_span,
+                });
+                None
+            }
             t => {
-                self.peek_errors(format!("No prefix parse function found for {}.", t).to_string());
+                self.peek_errors(ParseError::NoPrefixRule {
+                    token: t.clone(),
+                    span: self.current_span,
+                });
                 None
             }
         }
@@ -221,35 +446,77 @@ impl Parser {
 
     pub fn parse_infix_expression(&mut self, left_expr: Expression) -> Option<Expression> {
         let prefix_token = self.current_tok.clone();
-        let precedence = self.current_precedence();
+        let precedence = match prefix_token {
+            // Right associative: the right operand may itself be a power.
+            Token::POW => Precedence::PREFIX,
+            _ => self.current_precedence(),
+        };
         self.next_token();
 
-        if let Some(right_expr) = self.parse_expression(precedence) {
-            Some(Expression::Infix(InfixExpr::new(
-                prefix_token,
-                left_expr,
-                right_expr,
-            )))
-        } else {
-            None
+        let right_expr = self.parse_expression(precedence)?;
+        let span = self.span_from(left_expr.span());
+        Some(Expression::Infix(InfixExpr::new(
+            prefix_token,
+            left_expr,
+            right_expr,
+            span,
+        )))
+    }
+
+    pub fn parse_assign_expression(&mut self, target: Expression) -> Option<Expression> {
+        let operator = self.current_tok.clone();
+
+        if !matches!(target, Expression::Identifier(_) | Expression::Indexed(_)) {
+            self.peek_errors(ParseError::InvalidAssignmentTarget {
+                target: target.to_string(),
+                span: target.span(),
+            });
+            return None;
         }
+
+        // Right associative, `a = b = c` assigns `c` to both.
+        self.next_token();
+        let value = self.parse_expression(Precedence::LOWEST)?;
+        let span = self.span_from(target.span());
+        Some(Expression::Assign(AssignExpression::new(
+            operator, target, value, span,
+        )))
     }
 
     pub fn parse_infix(&mut self, token: &Token, expr: Expression) -> Option<Expression> {
         match token {
+            Token::AND => self.parse_infix_expression(expr),
+            Token::OR => self.parse_infix_expression(expr),
             Token::EQ => self.parse_infix_expression(expr),
             Token::NE => self.parse_infix_expression(expr),
             Token::LT => self.parse_infix_expression(expr),
             Token::GT => self.parse_infix_expression(expr),
+            Token::LE => self.parse_infix_expression(expr),
+            Token::GE => self.parse_infix_expression(expr),
+            Token::MOD => self.parse_infix_expression(expr),
+            Token::POW => self.parse_infix_expression(expr),
+            Token::BITAND => self.parse_infix_expression(expr),
+            Token::BITOR => self.parse_infix_expression(expr),
+            Token::BITXOR => self.parse_infix_expression(expr),
+            Token::SHL => self.parse_infix_expression(expr),
+            Token::SHR => self.parse_infix_expression(expr),
+            Token::DOTDOT => self.parse_infix_expression(expr),
+            Token::ASSIGN => self.parse_assign_expression(expr),
+            Token::PLUSASSIGN => self.parse_assign_expression(expr),
+            Token::SUBASSIGN => self.parse_assign_expression(expr),
+            Token::MULASSIGN => self.parse_assign_expression(expr),
+            Token::DIVASSIGN => self.parse_assign_expression(expr),
             Token::PLUS => self.parse_infix_expression(expr),
             Token::SUB => self.parse_infix_expression(expr),
             Token::MUL => self.parse_infix_expression(expr),
             Token::DIV => self.parse_infix_expression(expr),
             Token::LPAREN => self.parse_call_expression(expr),
             Token::LBRACKET => self.parse_index_expression(expr),
-            Token::ILLEGAL(_) => None,
             t => {
-                self.peek_errors(format!("No infix parse function found for {}.", t).to_string());
+                self.peek_errors(ParseError::NoInfixRule {
+                    token: t.clone(),
+                    span: self.current_span,
+                });
                 None
             }
         }
@@ -258,16 +525,18 @@ impl Parser {
     pub fn parse_grouped_expression(&mut self) -> Option<Expression> {
         self.next_token();
 
-        let expr = self.parse_expression(Precedence::LOWEST);
+        let expr = self.parse_expression(Precedence::LOWEST)?;
 
         if self.expect_token(&Token::RPAREN) {
-            expr
+            Some(expr)
         } else {
             None
         }
     }
 
     pub fn parse_if_expression(&mut self) -> Option<Expression> {
+        let start = self.current_span;
+
         if !self.expect_token(&Token::LPAREN) {
             return None;
         };
@@ -283,49 +552,51 @@ impl Parser {
             return None;
         };
 
-        let consequence = self.parse_block_statements();
+        let consequence = self.parse_block_statements()?;
 
         let alternative = if self.peek_token_is(&Token::ELSE) {
             self.next_token();
 
             if !self.expect_token(&Token::LBRACE) {
-                None
-            } else {
-                Some(self.parse_block_statements())
+                return None;
             }
+            Some(self.parse_block_statements()?)
         } else {
             None
         };
 
-        return Some(Expression::IfExpression(IfExpression {
+        Some(Expression::IfExpression(IfExpression {
             condition: Box::new(condition),
             consequence,
             alternative,
-        }));
+            span: self.span_from(start),
+        }))
     }
 
     pub fn parse_function_expression(&mut self) -> Option<Expression> {
+        let start = self.current_span;
+
         if !self.expect_token(&Token::LPAREN) {
             return None;
         };
 
-        let params = self.parse_function_parameters();
-        let parameters;
-
-        match params {
-            Some(p) => {
-                parameters = p;
-            }
-            None => return None,
-        }
+        let parameters = self.parse_function_parameters()?;
 
         if !self.expect_token(&Token::LBRACE) {
             return None;
         };
 
+        // Loops around a function literal do not extend into its body.
+        let loop_depth = std::mem::take(&mut self.loop_depth);
         let body = self.parse_block_statements();
+        self.loop_depth = loop_depth;
+        let body = body?;
 
-        Some(Expression::FnExpression(FnExpression { parameters, body }))
+        Some(Expression::FnExpression(FnExpression {
+            parameters,
+            body,
+            span: self.span_from(start),
+        }))
     }
 
     pub fn parse_function_parameters(&mut self) -> Option<Vec<Identifier>> {
@@ -336,17 +607,23 @@ impl Parser {
             return Some(parameters);
         };
 
-        self.next_token();
-        parameters.push(Identifier {
-            value: self.current_tok.to_string(),
-        });
+        if !self.expect_token(&Token::IDENT(String::new())) {
+            return None;
+        }
+        parameters.push(Identifier::new(
+            self.current_tok.to_string(),
+            self.current_span,
+        ));
 
         while self.peek_token_is(&Token::COMMA) {
             self.next_token();
-            self.next_token();
-            parameters.push(Identifier {
-                value: self.current_tok.to_string(),
-            });
+            if !self.expect_token(&Token::IDENT(String::new())) {
+                return None;
+            }
+            parameters.push(Identifier::new(
+                self.current_tok.to_string(),
+                self.current_span,
+            ));
         }
 
         if !self.expect_token(&Token::RPAREN) {
@@ -356,30 +633,34 @@ impl Parser {
         Some(parameters)
     }
 
-    pub fn parse_block_statements(&mut self) -> Vec<Statement> {
+    pub fn parse_block_statements(&mut self) -> Option<Vec<Statement>> {
+        let start = self.current_span;
         let mut block = vec![];
 
         self.next_token();
-        while !self.current_token_is(&Token::RBRACE) && !self.current_token_is(&Token::EOF) {
-            let stmt = self.parse_statement();
+        while !self.current_token_is(&Token::RBRACE) {
+            if self.current_token_is(&Token::EOF) {
+                self.peek_errors(ParseError::UnterminatedBlock { span: start });
+                return None;
+            }
 
-            match stmt {
+            match self.parse_statement() {
                 Some(stmt) => block.push(stmt),
-                None => (),
+                None => self.synchronize(),
             }
             self.next_token();
         }
-        block
+        Some(block)
     }
 
     pub fn parse_call_expression(&mut self, expr: Expression) -> Option<Expression> {
-        match self.parse_call_arguments() {
-            Some(arguments) => Some(Expression::CallExpression(CallExpression {
-                function: Box::new(expr),
-                arguments,
-            })),
-            None => None,
-        }
+        let arguments = self.parse_call_arguments()?;
+        let span = self.span_from(expr.span());
+        Some(Expression::CallExpression(CallExpression {
+            function: Box::new(expr),
+            arguments,
+            span,
+        }))
     }
 
     fn parse_list(&mut self, end_token: Token) -> Option<Vec<Expression>> {
@@ -427,8 +708,11 @@ impl Parser {
     }
 
     fn parse_arrays(&mut self) -> Option<Expression> {
+        let start = self.current_span;
+        let elements = self.parse_list(Token::RBRACKET)?;
         Some(Expression::Arrays(Arrays {
-            elements: self.parse_list(Token::RBRACKET)?,
+            elements,
+            span: self.span_from(start),
         }))
     }
 
@@ -440,9 +724,42 @@ impl Parser {
             return None;
         };
 
+        let span = self.span_from(expr.span());
         Some(Expression::Indexed(Indexed {
             left_expr: Box::new(expr),
             index: Box::new(index),
+            span,
+        }))
+    }
+
+    fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let start = self.current_span;
+        let mut pairs = vec![];
+
+        while !self.peek_token_is(&Token::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::LOWEST)?;
+
+            if !self.expect_token(&Token::COLON) {
+                return None;
+            }
+
+            self.next_token();
+            let value = self.parse_expression(Precedence::LOWEST)?;
+            pairs.push((key, value));
+
+            if !self.peek_token_is(&Token::RBRACE) && !self.expect_token(&Token::COMMA) {
+                return None;
+            }
+        }
+
+        if !self.expect_token(&Token::RBRACE) {
+            return None;
+        }
+
+        Some(Expression::HashLiteral(HashLiteral {
+            pairs,
+            span: self.span_from(start),
         }))
     }
 
@@ -450,6 +767,7 @@ impl Parser {
         match token_a {
             Token::IDENT(_) => matches!(token_b, Token::IDENT(_)),
             Token::INT(_) => matches!(token_b, Token::INT(_)),
+            Token::FLOAT(_) => matches!(token_b, Token::FLOAT(_)),
             Token::STRING(_) => matches!(token_b, Token::STRING(_)),
             a => *a == *token_b,
         }
@@ -468,18 +786,11 @@ impl Parser {
             self.next_token();
             true
         } else {
-            self.peek_errors(
-                std::format!(
-                    "expected token: {}\nreceived token: {}",
-                    token,
-                    self.peek_tok
-                )
-                .to_string(),
-            );
-
-            while !self.current_token_is(&Token::SEMICOLON) {
-                self.next_token();
-            }
+            self.peek_errors(ParseError::ExpectedToken {
+                expected: token.clone(),
+                found: self.peek_tok.clone(),
+                span: self.peek_span,
+            });
             false
         }
     }
@@ -492,7 +803,7 @@ impl Parser {
         token_to_precedence(self.current_tok.clone())
     }
 
-    pub fn peek_errors(&mut self, error: String) {
+    pub fn peek_errors(&mut self, error: ParseError) {
         self.errors.push(error);
     }
 }
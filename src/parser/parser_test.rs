@@ -2,12 +2,13 @@
 pub mod parser_test {
     use crate::{
         ast::ast::{
-            Arrays, CallExpression, Expression, FnExpression, Identifier, IfExpression, Indexed,
-            InfixExpr, LetStatement, PrefixExpr, ReturnStatement, Statement,
+            Arrays, CallExpression, Expression, FnExpression, HashLiteral, Identifier,
+            IfExpression, Indexed, InfixExpr, LetStatement, PrefixExpr, ReturnStatement, Statement,
         },
         lexer::lexer::Lexer,
-        parser::parser::Parser,
-        token::token::Token,
+        object::bigint::BigInt,
+        parser::{error::ParseError, parser::Parser},
+        token::token::{Position, Span, Token},
     };
 
     #[test]
@@ -21,20 +22,26 @@ let foobar = 838383;
             Statement::LetStatement(LetStatement {
                 name: Identifier {
                     value: "x".to_string(),
+                    span: Span::default(),
                 },
-                value: Expression::Integer(5),
+                value: Expression::Integer(5, Span::default()),
+                span: Span::default(),
             }),
             Statement::LetStatement(LetStatement {
                 name: Identifier {
                     value: "y".to_string(),
+                    span: Span::default(),
                 },
-                value: Expression::Integer(10),
+                value: Expression::Integer(10, Span::default()),
+                span: Span::default(),
             }),
             Statement::LetStatement(LetStatement {
                 name: Identifier {
                     value: "foobar".to_string(),
+                    span: Span::default(),
                 },
-                value: Expression::Integer(838383),
+                value: Expression::Integer(838383, Span::default()),
+                span: Span::default(),
             }),
         ];
 
@@ -67,13 +74,16 @@ return 838383;
 ";
         let expected_statements = vec![
             Statement::ReturnStatement(ReturnStatement {
-                value: Expression::Integer(5),
+                value: Expression::Integer(5, Span::default()),
+                span: Span::default(),
             }),
             Statement::ReturnStatement(ReturnStatement {
-                value: Expression::Integer(10),
+                value: Expression::Integer(10, Span::default()),
+                span: Span::default(),
             }),
             Statement::ReturnStatement(ReturnStatement {
-                value: Expression::Integer(838383),
+                value: Expression::Integer(838383, Span::default()),
+                span: Span::default(),
             }),
         ];
         test_parsing_statements(input, 0, expected_statements)
@@ -93,6 +103,7 @@ return 838383;
         let expected_statements = vec![Statement::ExpressionStatement(Expression::Identifier(
             Identifier {
                 value: "foobar".to_string(),
+                span: Span::default(),
             },
         ))];
 
@@ -103,7 +114,29 @@ return 838383;
     fn test_integer() {
         let input = "5;";
 
-        let expected_statements = vec![Statement::ExpressionStatement(Expression::Integer(5))];
+        let expected_statements = vec![Statement::ExpressionStatement(Expression::Integer(
+            5,
+            Span::default(),
+        ))];
+
+        test_parsing_statements(input, 0, expected_statements)
+    }
+
+    #[test]
+    fn test_big_integer() {
+        let input = "2147483647; 2147483648; 99999999999999999999;";
+
+        let expected_statements = vec![
+            Statement::ExpressionStatement(Expression::Integer(2147483647, Span::default())),
+            Statement::ExpressionStatement(Expression::BigInteger(
+                BigInt::parse("2147483648").unwrap(),
+                Span::default(),
+            )),
+            Statement::ExpressionStatement(Expression::BigInteger(
+                BigInt::parse("99999999999999999999").unwrap(),
+                Span::default(),
+            )),
+        ];
 
         test_parsing_statements(input, 0, expected_statements)
     }
@@ -113,19 +146,23 @@ return 838383;
         let input = "true; false; let foobar = true; let barfoo = false;";
 
         let expected_statements = vec![
-            Statement::ExpressionStatement(Expression::Bool(true)),
-            Statement::ExpressionStatement(Expression::Bool(false)),
+            Statement::ExpressionStatement(Expression::Bool(true, Span::default())),
+            Statement::ExpressionStatement(Expression::Bool(false, Span::default())),
             Statement::LetStatement(LetStatement {
                 name: Identifier {
                     value: "foobar".to_string(),
+                    span: Span::default(),
                 },
-                value: Expression::Bool(true),
+                value: Expression::Bool(true, Span::default()),
+                span: Span::default(),
             }),
             Statement::LetStatement(LetStatement {
                 name: Identifier {
                     value: "barfoo".to_string(),
+                    span: Span::default(),
                 },
-                value: Expression::Bool(false),
+                value: Expression::Bool(false, Span::default()),
+                span: Span::default(),
             }),
         ];
 
@@ -138,6 +175,7 @@ return 838383;
 
         let expected_statements = vec![Statement::ExpressionStatement(Expression::String(
             "hello world".to_string(),
+            Span::default(),
         ))];
 
         test_parsing_statements(input, 0, expected_statements)
@@ -148,9 +186,21 @@ return 838383;
         let input = "!5; -15; !false";
 
         let expected_statements = vec![
-            Expression::Prefix(PrefixExpr::new(Token::BANG, Expression::Integer(5))),
-            Expression::Prefix(PrefixExpr::new(Token::SUB, Expression::Integer(15))),
-            Expression::Prefix(PrefixExpr::new(Token::BANG, Expression::Bool(false))),
+            Expression::Prefix(PrefixExpr::new(
+                Token::BANG,
+                Expression::Integer(5, Span::default()),
+                Span::default(),
+            )),
+            Expression::Prefix(PrefixExpr::new(
+                Token::SUB,
+                Expression::Integer(15, Span::default()),
+                Span::default(),
+            )),
+            Expression::Prefix(PrefixExpr::new(
+                Token::BANG,
+                Expression::Bool(false, Span::default()),
+                Span::default(),
+            )),
         ]
         .iter()
         .map(|expr| build_stmt_from_expr(expr.clone()))
@@ -160,7 +210,11 @@ return 838383;
     }
 
     fn build_int_int_infix(token: Token, a: i32, b: i32) -> Expression {
-        build_infix_expr(token, Expression::Integer(a), Expression::Integer(b))
+        build_infix_expr(
+            token,
+            Expression::Integer(a, Span::default()),
+            Expression::Integer(b, Span::default()),
+        )
     }
 
     fn build_ident_ident_infix(token: Token, a: &str, b: &str) -> Expression {
@@ -168,7 +222,11 @@ return 838383;
     }
 
     fn build_bool_bool_infix(token: Token, a: bool, b: bool) -> Expression {
-        build_infix_expr(token, Expression::Bool(a), Expression::Bool(b))
+        build_infix_expr(
+            token,
+            Expression::Bool(a, Span::default()),
+            Expression::Bool(b, Span::default()),
+        )
     }
 
     fn build_stmt_from_expr(expr: Expression) -> Statement {
@@ -244,6 +302,7 @@ return 838383;
         v.iter()
             .map(|x| Identifier {
                 value: x.to_string(),
+                span: Span::default(),
             })
             .collect()
     }
@@ -263,10 +322,11 @@ return 838383;
             CallExpression {
                 function: Box::new(build_ident_expr("add")),
                 arguments: vec![
-                    Expression::Integer(1),
+                    Expression::Integer(1, Span::default()),
                     build_int_int_infix(Token::PLUS, 2, 3),
                     build_int_int_infix(Token::MUL, 4, 5),
                 ],
+                span: Span::default(),
             },
         ))];
 
@@ -293,6 +353,33 @@ return 838383;
             ),
             ("3 > 5 == false", "((3 > 5) == false);"),
             ("true == 3 < 5", "(true == (3 < 5));"),
+            ("a || b && c", "(a || (b && c));"),
+            ("a && b || c", "((a && b) || c);"),
+            ("a && b && c", "((a && b) && c);"),
+            ("a == b && !c", "((a == b) && (!c));"),
+            ("a < b || c + d > e", "((a < b) || ((c + d) > e));"),
+            ("a <= b == c >= d", "((a <= b) == (c >= d));"),
+            ("a * b % c", "((a * b) % c);"),
+            ("a ** b ** c", "(a ** (b ** c));"),
+            ("-a ** b", "(-(a ** b));"),
+            ("a ** -b * c", "((a ** (-b)) * c);"),
+            ("a * b ** c", "(a * (b ** c));"),
+            ("a | b ^ c & d", "(a | (b ^ (c & d)));"),
+            ("a & b == c", "((a & b) == c);"),
+            ("a << b + c >> d", "((a << (b + c)) >> d);"),
+            ("a & b << c", "(a & (b << c));"),
+            ("~a & b", "((~a) & b);"),
+            ("a | b && c", "((a | b) && c);"),
+            ("0..n - 1", "(0 .. (n - 1));"),
+            ("x = 5", "(x = 5);"),
+            ("x = y = 1 + 2", "(x = (y = (1 + 2)));"),
+            ("x += a || b", "(x += (a || b));"),
+            ("x -= 1; x *= 2; x /= 3", "(x -= 1);(x *= 2);(x /= 3);"),
+            ("f(x = 1)", "f((x = 1));"),
+            ("a[i] = b[j] + 1", "(a[i] = (b[j] + 1));"),
+            ("m[0][1] *= 2", "(m[0][1] *= 2);"),
+            ("a..b == c", "((a .. b) == c);"),
+            ("a < b..c | d", "(a < (b .. (c | d)));"),
             ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4);"),
             ("(5 + 5) * 2", "((5 + 5) * 2);"),
             ("2 / (5 + 5)", "(2 / (5 + 5));"),
@@ -322,24 +409,79 @@ return 838383;
             .for_each(|(i, e)| test_parsing_display_format(i, e));
     }
 
+    #[test]
+    fn parse_while_statements() {
+        let input_expect = [
+            ("while (x < 10) { x; }", "while (x < 10) { x; }"),
+            ("while (true) { break; };", "while true { break; }"),
+            (
+                "while (a) { if (b) { continue } while (c) { break } }",
+                "while a { if b { continue; };while c { break; } }",
+            ),
+        ];
+
+        input_expect.iter().for_each(|(i, e)| {
+            let mut parser = Parser::new(Lexer::new(*i));
+            let program = parser.parse_program();
+            check_parse_errors(&parser);
+            assert_eq!(program.to_string(), *e);
+        });
+
+        let mut parser = Parser::new(Lexer::new("while (x) { x }"));
+        let program = parser.parse_program();
+        match &program.statements[0] {
+            Statement::WhileStatement(while_statement) => {
+                assert_eq!(while_statement.span.start, Position::new(1, 1));
+                assert_eq!(while_statement.span.end, Position::new(1, 16));
+                assert_eq!(while_statement.body.len(), 1);
+            }
+            stmt => panic!("expected a while statement, got {stmt}"),
+        }
+    }
+
+    #[test]
+    fn parse_for_statements() {
+        let input_expect = [
+            ("for (x in [1, 2]) { x; }", "for x in [1, 2] { x; }"),
+            (
+                "for (i in 0..n) { break; };",
+                "for i in (0 .. n) { break; }",
+            ),
+            (
+                "while (a) { for (b in c) { continue } }",
+                "while a { for b in c { continue; } }",
+            ),
+        ];
+
+        input_expect.iter().for_each(|(i, e)| {
+            let mut parser = Parser::new(Lexer::new(*i));
+            let program = parser.parse_program();
+            check_parse_errors(&parser);
+            assert_eq!(program.to_string(), *e);
+        });
+    }
+
     #[test]
     fn parse_arrays() {
         let input = "[1, 2 * 2, 3 + 3]";
 
         let expected = vec![build_stmt_from_expr(Expression::Arrays(Arrays {
             elements: vec![
-                Expression::Integer(1),
+                Expression::Integer(1, Span::default()),
                 Expression::Infix(InfixExpr {
-                    left_expr: Box::new(Expression::Integer(2)),
+                    left_expr: Box::new(Expression::Integer(2, Span::default())),
                     operator: Token::MUL,
-                    right_expr: Box::new(Expression::Integer(2)),
+                    right_expr: Box::new(Expression::Integer(2, Span::default())),
+                    span: Span::default(),
                 }),
                 Expression::Infix(InfixExpr {
-                    left_expr: Box::new(Expression::Integer(3)),
+                    left_expr: Box::new(Expression::Integer(3, Span::default())),
                     operator: Token::PLUS,
-                    right_expr: Box::new(Expression::Integer(3)),
+                    right_expr: Box::new(Expression::Integer(3, Span::default())),
+                    span: Span::default(),
                 }),
             ],
+            span: Span::default(),
         }))];
 
         test_parsing_statements(input, 0, expected);
@@ -352,25 +494,245 @@ return 838383;
         let expected = vec![build_stmt_from_expr(Expression::Indexed(Indexed {
             left_expr: Box::new(Expression::Identifier(Identifier {
                 value: "myArray".to_string(),
+                span: Span::default(),
             })),
             index: Box::new(Expression::Infix(InfixExpr {
-                left_expr: Box::new(Expression::Integer(1)),
+                left_expr: Box::new(Expression::Integer(1, Span::default())),
                 operator: Token::PLUS,
-                right_expr: Box::new(Expression::Integer(1)),
+                right_expr: Box::new(Expression::Integer(1, Span::default())),
+                span: Span::default(),
             })),
+            span: Span::default(),
         }))];
 
         test_parsing_statements(input, 0, expected);
     }
 
+    #[test]
+    fn parse_hash_literals() {
+        let input = "{\"one\": 1, \"two\": 2 * 2, 3: true}";
+
+        let expected = vec![build_stmt_from_expr(Expression::HashLiteral(HashLiteral {
+            pairs: vec![
+                (
+                    Expression::String("one".to_string(), Span::default()),
+                    Expression::Integer(1, Span::default()),
+                ),
+                (
+                    Expression::String("two".to_string(), Span::default()),
+                    build_int_int_infix(Token::MUL, 2, 2),
+                ),
+                (
+                    Expression::Integer(3, Span::default()),
+                    Expression::Bool(true, Span::default()),
+                ),
+            ],
+            span: Span::default(),
+        }))];
+
+        test_parsing_statements(input, 0, expected);
+    }
+
+    #[test]
+    fn parse_empty_hash_literal() {
+        let expected = vec![build_stmt_from_expr(Expression::HashLiteral(HashLiteral {
+            pairs: vec![],
+            span: Span::default(),
+        }))];
+
+        test_parsing_statements("{}", 0, expected);
+    }
+
+    #[test]
+    fn parse_hash_literal_display() {
+        test_parsing_display_format("{\"a\": 1 + 2, b: c[0]}", "{a: (1 + 2), b: c[0]};");
+    }
+
+    #[test]
+    fn parse_node_spans() {
+        let input = "let x = 1;
+return add(x, y * 2);";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        check_parse_errors(&parser);
+
+        let let_stmt = &program.statements[0];
+        assert_eq!(let_stmt.span().start, Position::new(1, 1));
+        assert_eq!(let_stmt.span().end, Position::new(1, 10));
+
+        let return_stmt = &program.statements[1];
+        assert_eq!(return_stmt.span().start, Position::new(2, 1));
+        assert_eq!(return_stmt.span().end, Position::new(2, 21));
+
+        if let Statement::ReturnStatement(ReturnStatement {
+            value: Expression::CallExpression(call),
+            ..
+        }) = return_stmt
+        {
+            assert_eq!(call.span.start, Position::new(2, 8));
+            assert_eq!(call.span.end, Position::new(2, 21));
+
+            let infix = &call.arguments[1];
+            assert_eq!(infix.span().start, Position::new(2, 15));
+            assert_eq!(infix.span().end, Position::new(2, 20));
+        } else {
+            panic!("Should be a return statement of a call expression");
+        }
+    }
+
+    #[test]
+    fn parse_errors_report_position() {
+        let lexer = Lexer::new("let x = 1;\nlet = 10;");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 1);
+        assert_eq!(parser.errors()[0].span().start, Position::new(2, 5));
+    }
+
+    #[test]
+    fn parse_error_kinds() {
+        let span = |start, end| Span::new(Position::new(1, start), Position::new(1, end));
+        let input_expected = [
+            (
+                "let x 5;",
+                ParseError::ExpectedToken {
+                    expected: Token::ASSIGN,
+                    found: Token::INT("5".to_string()),
+                    span: span(7, 8),
+                },
+            ),
+            (
+                "let x = ;",
+                ParseError::NoPrefixRule {
+                    token: Token::SEMICOLON,
+                    span: span(9, 10),
+                },
+            ),
+            (
+                "1e999;",
+                ParseError::InvalidFloat {
+                    literal: "1e999".to_string(),
+                    span: span(1, 6),
+                },
+            ),
+            (
+                "let s = \"abc;",
+                ParseError::UnterminatedString { span: span(9, 14) },
+            ),
+            (
+                "let s = \"a\\qb\";",
+                ParseError::InvalidEscape {
+                    sequence: "\\q".to_string(),
+                    span: span(9, 15),
+                },
+            ),
+            (
+                "let x = 1 + @;",
+                ParseError::IllegalToken {
+                    literal: "@".to_string(),
+                    span: span(13, 14),
+                },
+            ),
+            (
+                "fn(x) { x + 1;",
+                ParseError::UnterminatedBlock { span: span(7, 8) },
+            ),
+            (
+                "while (true) { fn() { break; } }",
+                ParseError::OutsideLoop {
+                    keyword: Token::BREAK,
+                    span: span(23, 28),
+                },
+            ),
+            (
+                "while (true) { while (if (a) { continue } else { b }) {} }",
+                ParseError::OutsideLoop {
+                    keyword: Token::CONTINUE,
+                    span: span(32, 40),
+                },
+            ),
+        ];
+
+        input_expected.iter().for_each(|(input, expected)| {
+            let lexer = Lexer::new(*input);
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            assert_eq!(parser.errors().len(), 1);
+            assert_eq!(parser.errors()[0], *expected);
+        });
+    }
+
+    #[test]
+    fn parse_error_messages() {
+        let input_expected = [
+            ("let = 1;", "expected identifier, found ="),
+            ("let x 5;", "expected =, found 5"),
+            ("if (x { 1 }", "expected ), found {"),
+            ("let x = ;", "no prefix parse function found for ;"),
+            ("fn() {", "unterminated block, expected }"),
+            (r#""abc"#, r#"unterminated string, expected ""#),
+            (r#""\x""#, r"invalid escape sequence: \x"),
+            ("1 + /* 2", "unterminated comment, expected */"),
+            ("break;", "break outside of a loop"),
+            ("1 = 2", "invalid assignment target: 1"),
+            ("a + b = c", "invalid assignment target: (a + b)"),
+            ("f() += 1", "invalid assignment target: f()"),
+            ("if (x) { continue }", "continue outside of a loop"),
+            ("while (x { 1 }", "expected ), found {"),
+            ("for (x xs) {}", "expected IN, found xs"),
+            ("for (1 in xs) {}", "expected identifier, found 1"),
+            (
+                "for (x in xs) { fn() { continue } }",
+                "continue outside of a loop",
+            ),
+        ];
+
+        input_expected.iter().for_each(|(input, expected)| {
+            let lexer = Lexer::new(*input);
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            assert_eq!(parser.errors()[0].to_string(), *expected);
+        });
+    }
+
+    #[test]
+    fn parse_error_recovery() {
+        let input_expected = [
+            ("let x = 5 +; let y = 10; y;", 1, 2),
+            ("let x = (1 + 2; let y = 3;", 1, 1),
+            ("let a = fn() { let = 1; a }; let b = 2;", 1, 2),
+            ("let a = fn() { let x 1 }; let b = 2;", 1, 2),
+            ("if (x { 1 }; let y = 2;", 1, 1),
+            ("let = 1; let x 2; let y = 3; y", 2, 2),
+        ];
+
+        input_expected
+            .iter()
+            .for_each(|(input, errors, statements)| {
+                let lexer = Lexer::new(*input);
+                let mut parser = Parser::new(lexer);
+                let program = parser.parse_program();
+                check_parse_errors(&parser);
+
+                assert_eq!(parser.errors().len(), *errors);
+                assert_eq!(program.statements.len(), *statements);
+            });
+    }
+
     fn build_ident_expr(name: &str) -> Expression {
         Expression::Identifier(Identifier {
             value: name.to_string(),
+            span: Span::default(),
         })
     }
 
     fn build_infix_expr(token: Token, left: Expression, right: Expression) -> Expression {
-        Expression::Infix(InfixExpr::new(token, left, right))
+        Expression::Infix(InfixExpr::new(token, left, right, Span::default()))
     }
 
     fn build_if_expr(
@@ -382,6 +744,7 @@ return 838383;
             condition: Box::new(condition),
             consequence,
             alternative,
+            span: Span::default(),
         })
     }
 
@@ -391,9 +754,11 @@ return 838383;
                 .iter()
                 .map(|&s| Identifier {
                     value: s.to_string(),
+                    span: Span::default(),
                 })
                 .collect::<Vec<Identifier>>(),
             body,
+            span: Span::default(),
         })
     }
 
@@ -401,21 +766,123 @@ return 838383;
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
 
-        let program = parser.parse_program();
+        let mut program = parser.parse_program();
         check_parse_errors(&parser);
         assert_eq!(parser.errors().len(), errors);
         assert_eq!(program.statements.len(), expected_statements.len());
+        program
+            .statements
+            .iter_mut()
+            .for_each(clear_statement_spans);
         assert_eq!(program.statements, expected_statements);
     }
 
+    /// Resets the spans of `stmt` so it can be compared with nodes built by the tests.
+    fn clear_statement_spans(stmt: &mut Statement) {
+        match stmt {
+            Statement::LetStatement(ls) => {
+                ls.span = Span::default();
+                ls.name.span = Span::default();
+                clear_expression_spans(&mut ls.value);
+            }
+            Statement::ReturnStatement(rs) => {
+                rs.span = Span::default();
+                clear_expression_spans(&mut rs.value);
+            }
+            Statement::ExpressionStatement(expr) => clear_expression_spans(expr),
+            Statement::WhileStatement(ws) => {
+                ws.span = Span::default();
+                clear_expression_spans(&mut ws.condition);
+                ws.body.iter_mut().for_each(clear_statement_spans);
+            }
+            Statement::ForStatement(fs) => {
+                fs.span = Span::default();
+                fs.variable.span = Span::default();
+                clear_expression_spans(&mut fs.iterable);
+                fs.body.iter_mut().for_each(clear_statement_spans);
+            }
+            Statement::BreakStatement(span) | Statement::ContinueStatement(span) => {
+                *span = Span::default()
+            }
+        }
+    }
+
+    fn clear_expression_spans(expr: &mut Expression) {
+        match expr {
+            Expression::Identifier(ident) => ident.span = Span::default(),
+            Expression::Integer(_, span)
+            | Expression::BigInteger(_, span)
+            | Expression::Float(_, span)
+            | Expression::Bool(_, span)
+            | Expression::String(_, span) => *span = Span::default(),
+            Expression::Prefix(prefix) => {
+                prefix.span = Span::default();
+                clear_expression_spans(&mut prefix.expr);
+            }
+            Expression::Infix(infix) => {
+                infix.span = Span::default();
+                clear_expression_spans(&mut infix.left_expr);
+                clear_expression_spans(&mut infix.right_expr);
+            }
+            Expression::Assign(assign) => {
+                assign.span = Span::default();
+                clear_expression_spans(&mut assign.target);
+                clear_expression_spans(&mut assign.value);
+            }
+            Expression::IfExpression(ifexpr) => {
+                ifexpr.span = Span::default();
+                clear_expression_spans(&mut ifexpr.condition);
+                ifexpr
+                    .consequence
+                    .iter_mut()
+                    .for_each(clear_statement_spans);
+                if let Some(alternative) = &mut ifexpr.alternative {
+                    alternative.iter_mut().for_each(clear_statement_spans);
+                }
+            }
+            Expression::FnExpression(func) => {
+                func.span = Span::default();
+                func.parameters
+                    .iter_mut()
+                    .for_each(|param| param.span = Span::default());
+                func.body.iter_mut().for_each(clear_statement_spans);
+            }
+            Expression::CallExpression(call) => {
+                call.span = Span::default();
+                clear_expression_spans(&mut call.function);
+                call.arguments.iter_mut().for_each(clear_expression_spans);
+            }
+            Expression::Arrays(array) => {
+                array.span = Span::default();
+                array.elements.iter_mut().for_each(clear_expression_spans);
+            }
+            Expression::Indexed(index) => {
+                index.span = Span::default();
+                clear_expression_spans(&mut index.left_expr);
+                clear_expression_spans(&mut index.index);
+            }
+            Expression::HashLiteral(hash) => {
+                hash.span = Span::default();
+                hash.pairs.iter_mut().for_each(|(key, value)| {
+                    clear_expression_spans(key);
+                    clear_expression_spans(value);
+                });
+            }
+        }
+    }
+
     fn check_function_parameters(input: &str, errors: usize, expected_parameters: Vec<Identifier>) {
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
 
-        let program = parser.parse_program();
+        let mut program = parser.parse_program();
         check_parse_errors(&parser);
         assert_eq!(parser.errors().len(), errors);
         assert!(program.statements.len() > 0);
+        program
+            .statements
+            .iter_mut()
+            .for_each(clear_statement_spans);
 
         if let Statement::ExpressionStatement(Expression::FnExpression(func)) =
             program.statements.first().unwrap()
@@ -3,15 +3,28 @@ use std::fmt;
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Token {
     ILLEGAL(String),
+    /// String literal missing its closing quote.
+    UNTERMINATED,
+    /// String literal containing an unknown or malformed escape sequence.
+    BADESCAPE(String),
+    /// Block comment missing its closing `*/`.
+    OPENCOMMENT,
+    /// Comment text including its delimiters, only produced by `Lexer::with_comments`.
+    COMMENT(String),
     EOF,
 
     // Identifiers & literals
     IDENT(String), // foo, bar, x, y, ...
     INT(String),
+    FLOAT(String),
     STRING(String),
 
     // Operators
     ASSIGN,
+    PLUSASSIGN,
+    SUBASSIGN,
+    MULASSIGN,
+    DIVASSIGN,
     EQ,
     NE,
     BANG,
@@ -21,6 +34,19 @@ pub enum Token {
     MUL,
     GT,
     LT,
+    GE,
+    LE,
+    MOD,
+    POW,
+    AND,
+    OR,
+    BITAND,
+    BITOR,
+    BITXOR,
+    SHL,
+    SHR,
+    TILDE,
+    DOTDOT,
 
     // Delimiters
     COMMA,
@@ -41,6 +67,11 @@ pub enum Token {
     IF,
     ELSE,
     RETURN,
+    WHILE,
+    BREAK,
+    CONTINUE,
+    FOR,
+    IN,
     TRUE,
     FALSE,
 }
@@ -49,13 +80,24 @@ impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Token::ILLEGAL(s) => write!(f, "ILLEGAL : {}", s),
+            Token::UNTERMINATED => write!(f, "unterminated string"),
+            Token::BADESCAPE(s) => write!(f, "{}", s),
+            Token::OPENCOMMENT => write!(f, "unterminated comment"),
+            Token::COMMENT(s) => write!(f, "{}", s),
             Token::EOF => write!(f, "EOF"),
             Token::IDENT(s) => write!(f, "{}", s),
             Token::INT(s) => write!(f, "{}", s),
+            Token::FLOAT(s) => write!(f, "{}", s),
             Token::STRING(s) => write!(f, "{}", s),
             Token::ASSIGN => write!(f, "="),
+            Token::PLUSASSIGN => write!(f, "+="),
+            Token::SUBASSIGN => write!(f, "-="),
+            Token::MULASSIGN => write!(f, "*="),
+            Token::DIVASSIGN => write!(f, "/="),
             Token::EQ => write!(f, "=="),
             Token::NE => write!(f, "!="),
+            Token::AND => write!(f, "&&"),
+            Token::OR => write!(f, "||"),
             Token::BANG => write!(f, "!"),
             Token::PLUS => write!(f, "+"),
             Token::SUB => write!(f, "-"),
@@ -63,6 +105,17 @@ impl fmt::Display for Token {
             Token::MUL => write!(f, "*"),
             Token::GT => write!(f, ">"),
             Token::LT => write!(f, "<"),
+            Token::GE => write!(f, ">="),
+            Token::LE => write!(f, "<="),
+            Token::MOD => write!(f, "%"),
+            Token::POW => write!(f, "**"),
+            Token::BITAND => write!(f, "&"),
+            Token::BITOR => write!(f, "|"),
+            Token::BITXOR => write!(f, "^"),
+            Token::SHL => write!(f, "<<"),
+            Token::SHR => write!(f, ">>"),
+            Token::TILDE => write!(f, "~"),
+            Token::DOTDOT => write!(f, ".."),
             Token::COMMA => write!(f, ","),
             Token::SEMICOLON => write!(f, ";"),
             Token::COLON => write!(f, ":"),
@@ -78,8 +131,62 @@ impl fmt::Display for Token {
             Token::IF => write!(f, "IF"),
             Token::ELSE => write!(f, "ELSE"),
             Token::RETURN => write!(f, "RETURN"),
+            Token::WHILE => write!(f, "WHILE"),
+            Token::BREAK => write!(f, "BREAK"),
+            Token::CONTINUE => write!(f, "CONTINUE"),
+            Token::FOR => write!(f, "FOR"),
+            Token::IN => write!(f, "IN"),
             Token::TRUE => write!(f, "TRUE"),
             Token::FALSE => write!(f, "FALSE"),
         }
     }
 }
+
+/// A location in the source, both fields are 1-based and `column` counts characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Source range covered by a token or an AST node, `end` is exclusive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
@@ -0,0 +1,2 @@
+pub mod vm;
+pub mod vm_test;
@@ -0,0 +1,480 @@
+use std::{
+    cell::RefCell,
+    mem,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    compiler::code::{CompiledFunction, Instruction},
+    object::{
+        builtin::BuiltinFunction,
+        env::Environment,
+        error::{ErrorKind, Frame, InterruptHandle, RuntimeError},
+        gc::Collector,
+        iterator::ObjectIterator,
+        object::{Closure, HashPair, Object},
+        ops,
+    },
+    token::token::Token,
+};
+
+/// Nested calls allowed by default, tail calls do not count. Frames live on the heap, so this is
+/// higher than the `Evaluator` default, while still stopping runaway recursion long before it
+/// exhausts memory.
+pub const DEFAULT_MAX_DEPTH: usize = 100_000;
+
+struct CallFrame {
+    function: Rc<CompiledFunction>,
+    ip: usize,
+    env: Rc<RefCell<Environment>>,
+    /// Height of the value stack when the call started.
+    base: usize,
+    /// `None` for the program itself, which is not part of stack traces.
+    trace: Option<Frame>,
+    /// Stack heights recorded by `SetupLoop`, innermost loop last.
+    loops: Vec<usize>,
+    /// Iterators of the running `for` loops, innermost loop last.
+    iterators: Vec<ObjectIterator>,
+}
+
+/// Executes the output of the `Compiler`.
+///
+/// Calls are kept in a heap allocated frame stack, so recursion depth is not
+/// bounded by the native stack.
+pub struct Vm {
+    env: Rc<RefCell<Environment>>,
+    stack: Vec<Object>,
+    frames: Vec<CallFrame>,
+    max_depth: usize,
+    /// Instructions allowed by each `run`, the counterpart of the steps of the `Evaluator`.
+    step_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    steps: u64,
+    deadline: Option<Instant>,
+    interrupt: InterruptHandle,
+    /// Bytes allowed to be allocated by each `run`, counted like in the `Evaluator`.
+    memory_limit: Option<usize>,
+    allocated: usize,
+    collector: Collector,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Vm {
+    fn drop(&mut self) {
+        self.env = Rc::new(RefCell::new(Environment::new()));
+        self.collector.collect();
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        let mut collector = Collector::new();
+        Vm {
+            env: collector.track(Environment::new()),
+            stack: Vec::new(),
+            frames: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            step_limit: None,
+            time_limit: None,
+            steps: 0,
+            deadline: None,
+            interrupt: InterruptHandle::default(),
+            memory_limit: None,
+            allocated: 0,
+            collector,
+        }
+    }
+
+    /// Frees the environments only kept alive by cycles, like `Evaluator::collect_cycles`.
+    pub fn collect_cycles(&mut self) -> usize {
+        self.collector.collect()
+    }
+
+    /// Handle interrupting this VM, checked at every instruction.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Sets how many calls may be nested before a `StackOverflow` error, like the `Evaluator`.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Bounds the work done by each `run`, which fails with a `BudgetExhausted` error after
+    /// executing `steps` instructions.
+    pub fn with_step_limit(mut self, steps: u64) -> Self {
+        self.step_limit = Some(steps);
+        self
+    }
+
+    /// Bounds the duration of each `run`, which fails with a `BudgetExhausted` error once
+    /// `limit` has elapsed.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Gives each `run` an allocation budget, like `Evaluator::with_memory_limit`.
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    pub fn run(&mut self, program: CompiledFunction) -> Object {
+        self.steps = 0;
+        self.allocated = 0;
+        self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.stack.clear();
+        self.frames = vec![CallFrame {
+            function: Rc::new(program),
+            ip: 0,
+            env: Rc::clone(&self.env),
+            base: 0,
+            trace: None,
+            loops: Vec::new(),
+            iterators: Vec::new(),
+        }];
+
+        loop {
+            match self.step() {
+                Ok(None) => (),
+                Ok(Some(result)) => return result,
+                Err(mut error) => {
+                    let frame = self.frame();
+                    error.span = Some(frame.function.spans[frame.ip - 1]);
+                    error.stack = self
+                        .frames
+                        .iter()
+                        .filter_map(|frame| frame.trace.clone())
+                        .collect();
+                    self.stack.clear();
+                    self.frames.clear();
+                    return Object::ERROR(error);
+                }
+            }
+        }
+    }
+
+    /// Counts an instruction against the budget of the running `run`, and checks for interrupts.
+    fn count_step(&mut self) -> Result<(), RuntimeError> {
+        self.interrupt.check()?;
+        self.steps += 1;
+        if let Some(limit) = self.step_limit.filter(|limit| self.steps > *limit) {
+            return Err(RuntimeError::steps_exhausted(limit));
+        }
+        // Like in the `Evaluator`, the clock is only read every few steps.
+        if let Some(deadline) = self.deadline.filter(|_| self.steps.is_multiple_of(1024)) {
+            if Instant::now() >= deadline {
+                return Err(RuntimeError::time_exhausted(self.time_limit.unwrap()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `bytes` allocated by the running `run`, failing past the memory limit.
+    fn allocate(&mut self, bytes: usize) -> Result<(), RuntimeError> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        match self.memory_limit {
+            Some(limit) if self.allocated > limit => Err(RuntimeError::out_of_memory(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the bytes allocated for a new string or big integer.
+    fn allocate_value(&mut self, value: &Object) -> Result<(), RuntimeError> {
+        match value {
+            Object::STRING(s) => self.allocate(s.len()),
+            Object::BIGINT(i) => self.allocate(i.size()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Executes one instruction, returning the result of the program once it returns.
+    fn step(&mut self) -> Result<Option<Object>, RuntimeError> {
+        let frame = self.frames.last_mut().unwrap();
+        let instruction = frame.function.instructions[frame.ip];
+        frame.ip += 1;
+        self.count_step()?;
+
+        match instruction {
+            Instruction::Constant(index) => {
+                let constant = self.frame().function.constants[index as usize].clone();
+                self.allocate_value(&constant)?;
+                self.push(constant);
+            }
+            Instruction::True => self.push(Object::BOOLEAN(true)),
+            Instruction::False => self.push(Object::BOOLEAN(false)),
+            Instruction::Null => self.push(Object::NULL),
+            Instruction::Pop => {
+                self.pop();
+            }
+            Instruction::Add
+            | Instruction::Sub
+            | Instruction::Mul
+            | Instruction::Div
+            | Instruction::Equal
+            | Instruction::NotEqual
+            | Instruction::GreaterThan
+            | Instruction::LessThan
+            | Instruction::GreaterEqual
+            | Instruction::LessEqual
+            | Instruction::Mod
+            | Instruction::Pow
+            | Instruction::BitAnd
+            | Instruction::BitOr
+            | Instruction::BitXor
+            | Instruction::ShiftLeft
+            | Instruction::ShiftRight
+            | Instruction::Range => {
+                let right = self.pop();
+                let left = self.pop();
+                let operator = Self::operator(instruction);
+                let result = ops::eval_infix_expression(&operator, left, right);
+                self.allocate_value(&result)?;
+                self.push_result(result)?;
+            }
+            Instruction::Minus | Instruction::Bang | Instruction::BitNot => {
+                let operand = self.pop();
+                let operator = Self::operator(instruction);
+                let result = ops::eval_prefix_expression(&operator, operand);
+                self.allocate_value(&result)?;
+                self.push_result(result)?;
+            }
+            Instruction::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+            Instruction::JumpIfFalse(target) => {
+                let condition = self.pop();
+                if !ops::is_truthy(&condition) {
+                    self.frames.last_mut().unwrap().ip = target as usize;
+                }
+            }
+            Instruction::SetupLoop => {
+                let height = self.stack.len();
+                self.frames.last_mut().unwrap().loops.push(height);
+            }
+            Instruction::PopLoop => {
+                self.frames.last_mut().unwrap().loops.pop();
+            }
+            Instruction::Break(target) | Instruction::Continue(target) => {
+                let frame = self.frames.last_mut().unwrap();
+                self.stack.truncate(*frame.loops.last().unwrap());
+                frame.ip = target as usize;
+            }
+            Instruction::GetIter => {
+                let iterator = ObjectIterator::new(self.pop())?;
+                self.frames.last_mut().unwrap().iterators.push(iterator);
+            }
+            Instruction::ForIter(target) => {
+                let frame = self.frames.last_mut().unwrap();
+                match frame.iterators.last_mut().unwrap().next() {
+                    Some(value) => self.stack.push(value),
+                    None => frame.ip = target as usize,
+                }
+            }
+            Instruction::PopIter => {
+                self.frames.last_mut().unwrap().iterators.pop();
+            }
+            Instruction::GetName(index) => {
+                let frame = self.frame();
+                let name = &frame.function.names[index as usize];
+                let value = match frame.env.borrow().get(name) {
+                    Some(value) => value,
+                    None => BuiltinFunction::get_builtin(name).ok_or_else(|| {
+                        RuntimeError::new(
+                            ErrorKind::UnknownIdentifier,
+                            format!("identifier not found: {name}"),
+                        )
+                    })?,
+                };
+                self.push(value);
+            }
+            Instruction::SetName(index) => {
+                let frame = self.frame();
+                let name = frame.function.names[index as usize].clone();
+                let value = self.stack.last().unwrap().clone();
+                frame.env.borrow_mut().set(name, value);
+            }
+            Instruction::AssignName(index) => {
+                let frame = self.frame();
+                let name = &frame.function.names[index as usize];
+                let value = self.stack.last().unwrap().clone();
+                if !frame.env.borrow_mut().assign(name, value) {
+                    return Err(RuntimeError::new(
+                        ErrorKind::UnknownIdentifier,
+                        format!("identifier not found: {name}"),
+                    ));
+                }
+            }
+            Instruction::Array(len) => {
+                self.allocate(len as usize * mem::size_of::<Object>())?;
+                let elements = self.stack.split_off(self.stack.len() - len as usize);
+                self.push(Object::array(elements));
+            }
+            Instruction::Hash(len) => {
+                let mut items = self
+                    .stack
+                    .split_off(self.stack.len() - 2 * len as usize)
+                    .into_iter();
+                let mut pairs = vec![];
+                while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                    pairs.push((key, value));
+                }
+                self.allocate(pairs.len() * HashPair::SIZE)?;
+                self.push_result(ops::eval_hash_literal(pairs))?;
+            }
+            Instruction::Index => {
+                let index = self.pop();
+                let left = self.pop();
+                self.push_result(ops::eval_index_expression(left, index))?;
+            }
+            Instruction::SetIndex => {
+                let value = self.pop();
+                let index = self.pop();
+                let left = self.pop();
+                let entries = ops::hash_len(&left);
+                let assigned = ops::eval_index_assignment(left.clone(), index, value);
+                self.allocate((ops::hash_len(&left) - entries) * HashPair::SIZE)?;
+                self.push_result(assigned)?;
+            }
+            Instruction::DupPair => {
+                let pair = self.stack[self.stack.len() - 2..].to_vec();
+                self.stack.extend(pair);
+            }
+            Instruction::Closure(index) => {
+                let frame = self.frame();
+                let closure = Closure {
+                    function: Rc::clone(&frame.function.functions[index as usize]),
+                    env: Rc::clone(&frame.env),
+                };
+                self.push(Object::CLOSURE(closure));
+            }
+            Instruction::Call(argc, name) | Instruction::TailCall(argc, name) => {
+                let args = self.stack.split_off(self.stack.len() - argc as usize);
+                let callee = self.pop();
+                let tail = matches!(instruction, Instruction::TailCall(..));
+                self.call(callee, args, name, tail)?;
+            }
+            Instruction::ReturnValue => {
+                let value = self.pop();
+                let frame = self.frames.pop().unwrap();
+                if self.frames.is_empty() {
+                    return Ok(Some(value));
+                }
+                self.stack.truncate(frame.base);
+                self.push(value);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn call(
+        &mut self,
+        callee: Object,
+        args: Vec<Object>,
+        name: u32,
+        tail: bool,
+    ) -> Result<(), RuntimeError> {
+        match callee {
+            // The frame of the program itself is not a call.
+            Object::CLOSURE(_) if !tail && self.frames.len() > self.max_depth => {
+                Err(RuntimeError::stack_overflow(self.max_depth))
+            }
+            Object::CLOSURE(closure) => {
+                let frame = self.frame();
+                let trace = Frame {
+                    function: frame.function.names[name as usize].clone(),
+                    call_site: frame.function.spans[frame.ip - 1],
+                };
+
+                let mut env = Environment::new_enclosed(Rc::clone(&closure.env));
+                closure
+                    .function
+                    .parameters
+                    .iter()
+                    .zip(args)
+                    .for_each(|(param, arg)| env.set(param.clone(), arg));
+
+                // Only closures replace the caller, after other callees the instructions
+                // following the tail call return their result.
+                if tail {
+                    let caller = self.frames.pop().unwrap();
+                    self.stack.truncate(caller.base);
+                }
+                self.frames.push(CallFrame {
+                    function: closure.function,
+                    ip: 0,
+                    env: self.collector.track(env),
+                    base: self.stack.len(),
+                    trace: Some(trace),
+                    loops: Vec::new(),
+                    iterators: Vec::new(),
+                });
+                Ok(())
+            }
+            Object::BUILTIN(builtin) => {
+                let result = builtin.call(args);
+                self.allocate(builtin.allocated(&result))?;
+                self.push_result(result)
+            }
+            obj => Err(RuntimeError::new(
+                ErrorKind::NotCallable,
+                format!("not a function : {}", obj.get_type()),
+            )),
+        }
+    }
+
+    fn operator(instruction: Instruction) -> Token {
+        match instruction {
+            Instruction::Add => Token::PLUS,
+            Instruction::Sub | Instruction::Minus => Token::SUB,
+            Instruction::Mul => Token::MUL,
+            Instruction::Div => Token::DIV,
+            Instruction::Equal => Token::EQ,
+            Instruction::NotEqual => Token::NE,
+            Instruction::GreaterThan => Token::GT,
+            Instruction::LessThan => Token::LT,
+            Instruction::GreaterEqual => Token::GE,
+            Instruction::LessEqual => Token::LE,
+            Instruction::Mod => Token::MOD,
+            Instruction::Pow => Token::POW,
+            Instruction::BitAnd => Token::BITAND,
+            Instruction::BitOr => Token::BITOR,
+            Instruction::BitXor => Token::BITXOR,
+            Instruction::ShiftLeft => Token::SHL,
+            Instruction::ShiftRight => Token::SHR,
+            Instruction::Range => Token::DOTDOT,
+            Instruction::Bang => Token::BANG,
+            Instruction::BitNot => Token::TILDE,
+            _ => unreachable!("{instruction} is not an operator"),
+        }
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().unwrap()
+    }
+
+    fn push(&mut self, object: Object) {
+        self.stack.push(object);
+    }
+
+    fn push_result(&mut self, object: Object) -> Result<(), RuntimeError> {
+        match object {
+            Object::ERROR(error) => Err(error),
+            object => {
+                self.push(object);
+                Ok(())
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().unwrap()
+    }
+}
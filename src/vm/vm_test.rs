@@ -0,0 +1,46 @@
+#[cfg(test)]
+pub mod vm_test {
+    use crate::{
+        compiler::compiler::Compiler, lexer::lexer::Lexer, object::object::Object,
+        parser::parser::Parser, vm::vm::Vm,
+    };
+
+    fn run(vm: &mut Vm, input: &str) -> Object {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        vm.run(Compiler::new().compile(&program))
+    }
+
+    #[test]
+    fn deep_recursion() {
+        let input = "let count = fn(n) { if (n == 0) { return 0; } 1 + count(n - 1) };
+count(50000)";
+
+        assert_eq!(run(&mut Vm::new(), input), Object::INTEGER(50000));
+    }
+
+    #[test]
+    fn globals_persist_between_runs() {
+        let mut vm = Vm::new();
+        run(&mut vm, "let a = 5; let double = fn(x) { x * 2 };");
+
+        assert_eq!(run(&mut vm, "double(a)"), Object::INTEGER(10));
+    }
+
+    #[test]
+    fn state_is_reset_after_error() {
+        let mut vm = Vm::new();
+        let input_expctdvalue = [
+            ("let f = fn(x) { x + true }; f(1)", None),
+            ("f", Some("FUNCTION")),
+            ("1 + 1", Some("INTEGER")),
+        ];
+
+        input_expctdvalue.iter().for_each(|(i, v)| {
+            let result = run(&mut vm, i);
+            match v {
+                Some(t) => assert_eq!(result.get_type(), *t),
+                None => assert!(matches!(result, Object::ERROR(_))),
+            }
+        });
+    }
+}